@@ -0,0 +1,306 @@
+// telling plaintext apart from garbage. crackers try every key, score each
+// candidate plaintext with a PlaintextScorer and keep the best one
+
+pub mod ngram;
+
+// something that can rank candidate plaintexts.
+// the higher the score, the "more likely" the plaintext is
+pub trait PlaintextScorer {
+    fn score(&self, plaintext: &[u8]) -> f64;
+
+    // scorers that look at each byte together with the bytes before it, like
+    // ngram::NgramScorer, return how many bytes before it. their score must be
+    // a sum of one term per byte that depends on that byte and this many before it.
+    // crackers use this to rescore only the part of a plaintext a key byte changed
+    fn context(&self) -> usize {
+        return 0;
+    }
+}
+
+// relative frequencies of the letters and space in English text
+const ENGLISH_LETTER_FREQ: [(u8, f64); 27] = [
+    (b'a', 0.0651738),
+    (b'b', 0.0124248),
+    (b'c', 0.0217339),
+    (b'd', 0.0349835),
+    (b'e', 0.1041442),
+    (b'f', 0.0197881),
+    (b'g', 0.0158610),
+    (b'h', 0.0492888),
+    (b'i', 0.0558094),
+    (b'j', 0.0009033),
+    (b'k', 0.0050529),
+    (b'l', 0.0331490),
+    (b'm', 0.0202124),
+    (b'n', 0.0564513),
+    (b'o', 0.0596302),
+    (b'p', 0.0137645),
+    (b'q', 0.0008606),
+    (b'r', 0.0497563),
+    (b's', 0.0515760),
+    (b't', 0.0729357),
+    (b'u', 0.0225134),
+    (b'v', 0.0082903),
+    (b'w', 0.0171272),
+    (b'x', 0.0013692),
+    (b'y', 0.0145984),
+    (b'z', 0.0007836),
+    (b' ', 0.1918182)
+];
+
+// ENGLISH_LETTER_FREQ indexed by byte. upper case letters get the
+// frequency of their lower case twin; every other byte is 0
+pub fn english_letter_freq() -> [f64; 256] {
+    let mut table = english_letter_dist();
+    for &(c, freq) in ENGLISH_LETTER_FREQ.iter() {
+        table[c.to_ascii_uppercase() as usize] = freq;
+    }
+    return table;
+}
+
+// ENGLISH_LETTER_FREQ indexed by byte, lower case only. the distribution scorers
+// fold upper case into lower case, so english_letter_freq would count letters twice
+fn english_letter_dist() -> [f64; 256] {
+    let mut table = [0.0; 256];
+    for &(c, freq) in ENGLISH_LETTER_FREQ.iter() {
+        table[c as usize] = freq;
+    }
+    return table;
+}
+
+// assumption: characters are independent samples from the distribution in the table.
+// the score is the sum of the frequencies of the plaintext's bytes. bytes that
+// are not in the table add nothing, which penalizes plaintexts without letters
+#[derive(Clone)]
+pub struct LetterFrequencyScorer {
+    table: [f64; 256],
+}
+
+impl LetterFrequencyScorer {
+    pub fn new(table: [f64; 256]) -> LetterFrequencyScorer {
+        return LetterFrequencyScorer { table };
+    }
+
+    pub fn english() -> LetterFrequencyScorer {
+        return LetterFrequencyScorer::new(english_letter_freq());
+    }
+
+    pub fn table(&self) -> &[f64; 256] {
+        return &self.table;
+    }
+}
+
+impl Default for LetterFrequencyScorer {
+    fn default() -> LetterFrequencyScorer {
+        return LetterFrequencyScorer::english();
+    }
+}
+
+impl PlaintextScorer for LetterFrequencyScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        return plaintext.iter().map(|&b| self.table[b as usize]).sum();
+    }
+}
+
+// the scorers below compare the plaintext's byte histogram with a probability
+// distribution. like LetterFrequencyScorer they fold upper case into lower case,
+// and bytes the table has no frequency for get floor instead of 0, so that a
+// stray digit or comma is unlikely rather than impossible
+pub const DEFAULT_FLOOR: f64 = 0.0001;
+
+// table as a distribution over case-folded bytes: each upper case letter's
+// frequency is added to its lower case twin, and then upper case letters get 0
+// (they never occur after folding), every other byte at least floor, summing to 1
+fn folded_distribution(table: &[f64; 256], floor: f64) -> [f64; 256] {
+    let mut dist = [0.0; 256];
+    for (b, &freq) in table.iter().enumerate() {
+        dist[(b as u8).to_ascii_lowercase() as usize] += freq;
+    }
+
+    for (b, p) in dist.iter_mut().enumerate() {
+        if !(b as u8).is_ascii_uppercase() {
+            *p = p.max(floor);
+        }
+    }
+
+    let total: f64 = dist.iter().sum();
+    dist.iter_mut().for_each(|p| *p /= total);
+    return dist;
+}
+
+// how often each byte occurs in plaintext, with upper case folded into lower case
+fn folded_counts(plaintext: &[u8]) -> [f64; 256] {
+    let mut counts = [0.0; 256];
+    for b in plaintext {
+        counts[b.to_ascii_lowercase() as usize] += 1.0;
+    }
+    return counts;
+}
+
+// Pearson's chi-squared statistic between the plaintext's histogram and the
+// histogram the distribution predicts for a text of its length, negated so
+// that closer is higher. sensitive to rare bytes: one byte at the floor costs a lot
+#[derive(Clone)]
+pub struct ChiSquaredScorer {
+    dist: [f64; 256],
+}
+
+impl ChiSquaredScorer {
+    pub fn new(table: &[f64; 256], floor: f64) -> ChiSquaredScorer {
+        return ChiSquaredScorer { dist: folded_distribution(table, floor) };
+    }
+
+    pub fn english() -> ChiSquaredScorer {
+        return ChiSquaredScorer::new(&english_letter_dist(), DEFAULT_FLOOR);
+    }
+}
+
+impl PlaintextScorer for ChiSquaredScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        let n = plaintext.len() as f64;
+        let counts = folded_counts(plaintext);
+
+        let mut chi_squared: f64 = 0.0;
+        for (&observed, &p) in counts.iter().zip(self.dist.iter()) {
+            if p > 0.0 {
+                let expected = n * p;
+                chi_squared += (observed - expected) * (observed - expected) / expected;
+            }
+        }
+        return -chi_squared;
+    }
+}
+
+// the log probability of the plaintext, one independent byte at a time.
+// the floor keeps a single unexpected byte from making the score -infinity
+#[derive(Clone)]
+pub struct LogLikelihoodScorer {
+    log_probs: [f64; 256],
+}
+
+impl LogLikelihoodScorer {
+    pub fn new(table: &[f64; 256], floor: f64) -> LogLikelihoodScorer {
+        let dist = folded_distribution(table, floor);
+        let mut log_probs = [0.0; 256];
+        for (log_p, p) in log_probs.iter_mut().zip(dist.iter()) {
+            *log_p = p.ln();
+        }
+        return LogLikelihoodScorer { log_probs };
+    }
+
+    pub fn english() -> LogLikelihoodScorer {
+        return LogLikelihoodScorer::new(&english_letter_dist(), DEFAULT_FLOOR);
+    }
+}
+
+impl PlaintextScorer for LogLikelihoodScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        return plaintext.iter().map(|b| self.log_probs[b.to_ascii_lowercase() as usize]).sum();
+    }
+}
+
+// the Bhattacharyya coefficient between the plaintext's byte distribution and
+// the expected one: 1 for identical distributions, 0 for ones with nothing in common.
+// it does not grow with the length of the plaintext
+#[derive(Clone)]
+pub struct BhattacharyyaScorer {
+    sqrt_probs: [f64; 256],
+}
+
+impl BhattacharyyaScorer {
+    pub fn new(table: &[f64; 256], floor: f64) -> BhattacharyyaScorer {
+        let dist = folded_distribution(table, floor);
+        let mut sqrt_probs = [0.0; 256];
+        for (sqrt_p, p) in sqrt_probs.iter_mut().zip(dist.iter()) {
+            *sqrt_p = p.sqrt();
+        }
+        return BhattacharyyaScorer { sqrt_probs };
+    }
+
+    pub fn english() -> BhattacharyyaScorer {
+        return BhattacharyyaScorer::new(&english_letter_dist(), DEFAULT_FLOOR);
+    }
+}
+
+impl PlaintextScorer for BhattacharyyaScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        if plaintext.is_empty() {
+            return 0.0;
+        }
+
+        let n = plaintext.len() as f64;
+        let counts = folded_counts(plaintext);
+        return counts.iter().zip(self.sqrt_probs.iter()).map(|(&c, &sqrt_p)| (c / n).sqrt() * sqrt_p).sum();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::analysis::ngram::FrequencyTable;
+
+    #[test]
+    fn test_letter_frequency_scorer() {
+        let scorer = LetterFrequencyScorer::english();
+        assert_eq!(scorer.score(b"Hello World"), scorer.score(b"hello world"));
+        assert_eq!(scorer.score(b"\x00\x01\xff{}"), 0.0);
+        assert_eq!(scorer.score(b""), 0.0);
+
+        let english = b"Cooking MC's like a pound of bacon";
+        let xored: Vec<u8> = english.iter().map(|b| b ^ 0x58).collect();
+        assert!(scorer.score(english) > scorer.score(&xored));
+
+        // any table works, e.g. one that only likes digits
+        let mut digits = [0.0; 256];
+        digits[b'0' as usize ..= b'9' as usize].iter_mut().for_each(|f| *f = 0.1);
+        let scorer: &dyn PlaintextScorer = &LetterFrequencyScorer::new(digits);
+        assert!(scorer.score(b"8675309") > scorer.score(b"jenny"));
+    }
+
+    #[test]
+    fn test_distribution_scorers() {
+        let english = b"Cooking MC's like a pound of bacon";
+        let xored: Vec<u8> = english.iter().map(|b| b ^ 0x58).collect();
+        let scorers: [&dyn PlaintextScorer; 3] = [&ChiSquaredScorer::english(), &LogLikelihoodScorer::english(), &BhattacharyyaScorer::english()];
+        for scorer in scorers.iter() {
+            assert!(scorer.score(english) > scorer.score(&xored));
+            assert_eq!(scorer.score(b"Hello World"), scorer.score(b"hello world"));
+            assert!(scorer.score(b"\x00\x01\xff{}").is_finite());
+        }
+
+        // the letter frequency sum prefers the most common bytes over English, and so
+        // does log-likelihood: the most likely text is the most common byte repeated.
+        // the scorers that compare whole histograms do not
+        let sentence = b"Now that the party is jumping";
+        let degenerate = b"e e e e e e e e e e e e e e e";
+        assert!(LetterFrequencyScorer::english().score(sentence) < LetterFrequencyScorer::english().score(degenerate));
+        assert!(LogLikelihoodScorer::english().score(sentence) < LogLikelihoodScorer::english().score(degenerate));
+        assert!(ChiSquaredScorer::english().score(sentence) > ChiSquaredScorer::english().score(degenerate));
+        assert!(BhattacharyyaScorer::english().score(sentence) > BhattacharyyaScorer::english().score(degenerate));
+
+        let coefficient = BhattacharyyaScorer::english().score(english);
+        assert!(coefficient > 0.0 && coefficient <= 1.0);
+    }
+
+    #[test]
+    fn test_folded_distribution() {
+        // a table trained on upper case text folds to the same distribution as
+        // one trained on the same text in lower case, rather than to only the space
+        let upper = FrequencyTable::train(&b"ATTACK AT DAWN"[..], 1).unwrap().frequencies();
+        let lower = FrequencyTable::train(&b"attack at dawn"[..], 1).unwrap().frequencies();
+        assert_eq!(folded_distribution(&upper, DEFAULT_FLOOR)[..], folded_distribution(&lower, DEFAULT_FLOOR)[..]);
+
+        let scorers: [&dyn PlaintextScorer; 3] = [&ChiSquaredScorer::new(&upper, DEFAULT_FLOOR),
+            &LogLikelihoodScorer::new(&upper, DEFAULT_FLOOR), &BhattacharyyaScorer::new(&upper, DEFAULT_FLOOR)];
+        for scorer in scorers.iter() {
+            assert!(scorer.score(b"attack at dusk") > scorer.score(b"qzxjvqy qz qzxj"));
+            assert_eq!(scorer.score(b"Attack at Dawn"), scorer.score(b"attack at dawn"));
+        }
+
+        // the English scorers count each letter once, not once per case
+        let dist = ChiSquaredScorer::english().dist;
+        let expected = 0.1041442 / 0.1918182;
+        assert!((dist[b'e' as usize] / dist[b' ' as usize] - expected).abs() < 1e-9);
+        assert_eq!(dist[b'E' as usize], 0.0);
+    }
+}
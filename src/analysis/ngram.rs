@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use crate::analysis::PlaintextScorer;
+
+// n-gram counts and a scorer built on them. unigram scorers cannot tell "etaoin"
+// from "eat on", which matters for short ciphertexts; an n-gram model can
+
+// n-grams are packed into a u64 for lookups, so this is the longest one a table can hold
+pub const MAX_NGRAM_LEN: usize = 8;
+
+// the first line after any comments of a table file: "ngrams <n> <total>"
+const HEADER_TAG: &str = "ngrams";
+
+// how often each n-byte sequence occurred in some corpus. total counts every
+// n-gram of the corpus, including any that were left out of the table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrequencyTable {
+    n: usize,
+    total: u64,
+    counts: HashMap<u64, u64>,
+}
+
+// the bytes of ngram as one big-endian number
+fn pack(ngram: &[u8]) -> u64 {
+    return ngram.iter().fold(0, |acc, &b| (acc << 8) | b as u64);
+}
+
+// the n-gram of len bytes that pack() turned into packed
+fn unpack(packed: u64, len: usize) -> Vec<u8> {
+    return packed.to_be_bytes()[8-len..].to_vec();
+}
+
+// the last len bytes of a window packed as by pack()
+fn suffix(window: u64, len: usize) -> u64 {
+    return window & (u64::MAX >> (64 - 8*len));
+}
+
+fn invalid_data(line_num: usize, msg: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, format!("n-gram table line {}: {}", line_num, msg));
+}
+
+// undo <[u8]>::escape_ascii, which table files use to keep n-grams printable
+fn unescape(field: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(field.len());
+    let mut iter = field.bytes();
+    while let Some(b) = iter.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+
+        let unescaped = match iter.next()? {
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'n' => b'\n',
+            b'x' => {
+                let hex = [iter.next()?, iter.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            },
+            c @ (b'\'' | b'"' | b'\\') => c,
+            _ => return None,
+        };
+        bytes.push(unescaped);
+    }
+    return Some(bytes);
+}
+
+impl FrequencyTable {
+    pub fn n(&self) -> usize {
+        return self.n;
+    }
+
+    pub fn total(&self) -> u64 {
+        return self.total;
+    }
+
+    // how often ngram occurred; 0 if it did not, or is not n bytes long
+    pub fn count(&self, ngram: &[u8]) -> u64 {
+        if ngram.len() != self.n {
+            return 0;
+        }
+        return self.counts.get(&pack(ngram)).cloned().unwrap_or(0);
+    }
+
+    // count the n-grams of a corpus: any text or binary data, read to the end.
+    // fails with InvalidInput unless n is 1 to MAX_NGRAM_LEN
+    pub fn train(reader: impl Read, n: usize) -> io::Result<FrequencyTable> {
+        let mut tables = FrequencyTable::train_up_to(reader, n)?;
+        // train_up_to returns n >= 1 tables
+        return Ok(tables.pop().unwrap());
+    }
+
+    // the 1-gram to n-gram tables of a corpus in one pass over it, as NgramScorer::new takes them
+    pub fn train_up_to(reader: impl Read, n: usize) -> io::Result<Vec<FrequencyTable>> {
+        if !(1..=MAX_NGRAM_LEN).contains(&n) {
+            let msg = format!("n-grams must be 1 to {} bytes, not {}", MAX_NGRAM_LEN, n);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+
+        let mut tables: Vec<FrequencyTable> = (1..=n)
+            .map(|k| FrequencyTable { n: k, total: 0, counts: HashMap::new() })
+            .collect();
+
+        let mut window: u64 = 0;
+        let mut seen: usize = 0;
+        for b in BufReader::new(reader).bytes() {
+            window = (window << 8) | b? as u64;
+            seen += 1;
+
+            // the first few bytes do not have a full window behind them yet
+            for table in tables.iter_mut().take(seen) {
+                *table.counts.entry(suffix(window, table.n)).or_insert(0) += 1;
+                table.total += 1;
+            }
+        }
+
+        return Ok(tables);
+    }
+
+    // drop the n-grams seen fewer than min_count times. total is unchanged, so
+    // the frequencies of the n-grams that are left stay the same
+    pub fn prune(&mut self, min_count: u64) {
+        self.counts.retain(|_, &mut count| count >= min_count);
+    }
+
+    // the relative frequency of every byte, for the unigram scorers. only for 1-gram tables
+    pub fn frequencies(&self) -> [f64; 256] {
+        assert_eq!(self.n, 1, "frequencies are per byte, so the table must be of 1-grams");
+
+        let mut freqs = [0.0; 256];
+        if self.total > 0 {
+            for (&b, &count) in self.counts.iter() {
+                freqs[b as usize] = count as f64 / self.total as f64;
+            }
+        }
+        return freqs;
+    }
+
+    // write the table in the format load() reads, most common n-grams first
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut entries: Vec<(u64, u64)> = self.counts.iter().map(|(&ngram, &count)| (ngram, count)).collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        writeln!(writer, "{} {} {}", HEADER_TAG, self.n, self.total)?;
+        for (ngram, count) in entries {
+            writeln!(writer, "{}\t{}", unpack(ngram, self.n).escape_ascii(), count)?;
+        }
+        return writer.flush();
+    }
+
+    // read a table file: '#' comment lines, the header "ngrams <n> <total>", then
+    // one "<n-gram>\t<count>" line per n-gram, with the n-gram escaped by escape_ascii
+    pub fn load(reader: impl Read) -> io::Result<FrequencyTable> {
+        let mut header: Option<(usize, u64)> = None;
+        let mut counts: HashMap<u64, u64> = HashMap::new();
+
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line_num = i + 1;
+            // an n-gram line can start with '#' too, so comments end at the header
+            if header.is_none() && line.starts_with('#') {
+                continue;
+            }
+
+            match header {
+                None => {
+                    let fields: Vec<&str> = line.split(' ').collect();
+                    let n: Option<usize> = fields.get(1).and_then(|f| f.parse().ok());
+                    let total: Option<u64> = fields.get(2).and_then(|f| f.parse().ok());
+                    match (fields.len(), fields[0], n, total) {
+                        (3, HEADER_TAG, Some(n), Some(total)) if (1..=MAX_NGRAM_LEN).contains(&n) => {
+                            header = Some((n, total));
+                        },
+
+                        _ => return Err(invalid_data(line_num, "expected \"ngrams <n> <total>\"")),
+                    }
+                },
+
+                Some((n, _)) => {
+                    let (ngram, count) = line.split_once('\t')
+                        .ok_or_else(|| invalid_data(line_num, "expected \"<n-gram>\\t<count>\""))?;
+                    let ngram = unescape(ngram)
+                        .filter(|ngram| ngram.len() == n)
+                        .ok_or_else(|| invalid_data(line_num, "bad n-gram"))?;
+                    let count: u64 = count.parse()
+                        .map_err(|_| invalid_data(line_num, "bad count"))?;
+                    counts.insert(pack(&ngram), count);
+                },
+            }
+        }
+
+        let (n, total) = header.ok_or_else(|| invalid_data(0, "missing header"))?;
+        return Ok(FrequencyTable { n, total, counts });
+    }
+
+    // the tables in data/, counted over English prose. None unless n is 1 to 4
+    pub fn english(n: usize) -> Option<FrequencyTable> {
+        let file: &[u8] = match n {
+            1 => include_bytes!("../../data/english_unigrams.txt"),
+            2 => include_bytes!("../../data/english_bigrams.txt"),
+            3 => include_bytes!("../../data/english_trigrams.txt"),
+            4 => include_bytes!("../../data/english_quadgrams.txt"),
+            _ => return None,
+        };
+        // the files are part of the crate and known to load
+        return Some(FrequencyTable::load(file).unwrap());
+    }
+}
+
+// the scorer looks up every byte of every candidate plaintext, and its keys are
+// already well-mixed packed n-grams, so a multiply beats SipHash by a wide margin
+#[derive(Default)]
+struct NgramHasher(u64);
+
+impl Hasher for NgramHasher {
+    fn finish(&self) -> u64 {
+        return self.0;
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(b as u64);
+        }
+    }
+
+    fn write_u64(&mut self, x: u64) {
+        self.0 = (self.0.rotate_left(5) ^ x).wrapping_mul(0x9e3779b97f4a7c15);
+    }
+}
+
+type NgramMap<V> = HashMap<u64, V, BuildHasherDefault<NgramHasher>>;
+
+// an unseen n-gram backs off to the next shorter one at this penalty
+const BACKOFF: f64 = 0.4;
+
+// the log probability of each byte given the up to n-1 bytes before it, with
+// "stupid backoff" (Brants et al. 2007) for contexts the tables have not seen:
+// P(c | ab) = count(abc) / count(ab) if abc was seen, otherwise BACKOFF * P(c | b).
+// unigrams are add-one smoothed, so no byte has probability 0
+#[derive(Clone)]
+pub struct NgramScorer {
+    unigrams: [f64; 256],
+    // log_probs[k] maps each seen (k+2)-gram to log P(last byte | the others)
+    log_probs: Vec<NgramMap<f64>>,
+    log_backoff: f64,
+}
+
+impl NgramScorer {
+    // tables[k] holds the (k+1)-grams; the scorer is of order tables.len()
+    pub fn new(tables: &[FrequencyTable]) -> NgramScorer {
+        assert!(!tables.is_empty(), "NgramScorer needs at least a unigram table");
+        for (k, table) in tables.iter().enumerate() {
+            assert_eq!(table.n(), k+1, "tables must be for 1-grams, 2-grams, ... in order");
+        }
+
+        let mut unigrams = [0.0; 256];
+        for (b, log_p) in unigrams.iter_mut().enumerate() {
+            let count = tables[0].count(&[b as u8]) as f64;
+            *log_p = ((count + 1.0) / (tables[0].total() as f64 + 256.0)).ln();
+        }
+
+        let mut log_probs: Vec<NgramMap<f64>> = Vec::new();
+        for pair in tables.windows(2) {
+            let (context, table) = (&pair[0], &pair[1]);
+            let mut order_log_probs: NgramMap<f64> = NgramMap::default();
+            for (&ngram, &count) in table.counts.iter() {
+                // every n-gram of a table has its context in the next shorter table,
+                // unless the tables were pruned differently
+                let context_count = context.counts.get(&(ngram >> 8)).cloned().unwrap_or(0);
+                if count > 0 && context_count >= count {
+                    order_log_probs.insert(ngram, (count as f64 / context_count as f64).ln());
+                }
+            }
+            log_probs.push(order_log_probs);
+        }
+
+        return NgramScorer { unigrams, log_probs, log_backoff: BACKOFF.ln() };
+    }
+
+    // the scorer over the English tables in data/, of order n. None unless n is 1 to 4
+    pub fn english(n: usize) -> Option<NgramScorer> {
+        if n == 0 {
+            return None;
+        }
+
+        let tables: Vec<FrequencyTable> = (1..=n).map(FrequencyTable::english).collect::<Option<_>>()?;
+        return Some(NgramScorer::new(&tables));
+    }
+
+    pub fn order(&self) -> usize {
+        return self.log_probs.len() + 1;
+    }
+
+    // log P(last byte of window | the rest of window), for a window of len bytes
+    // packed as by pack(). its shorter suffixes are just its low bytes
+    fn log_prob(&self, window: u64, len: usize) -> f64 {
+        let mut penalty: f64 = 0.0;
+        for m in (2..=len).rev() {
+            if let Some(log_p) = self.log_probs[m-2].get(&suffix(window, m)) {
+                return penalty + log_p;
+            }
+            penalty += self.log_backoff;
+        }
+        return penalty + self.unigrams[(window & 0xff) as usize];
+    }
+}
+
+impl PlaintextScorer for NgramScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        let n = self.order();
+        let mut window: u64 = 0;
+        let mut score: f64 = 0.0;
+        for (i, &b) in plaintext.iter().enumerate() {
+            window = (window << 8) | b as u64;
+            score += self.log_prob(window, n.min(i+1));
+        }
+        return score;
+    }
+
+    fn context(&self) -> usize {
+        return self.order() - 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::analysis::LogLikelihoodScorer;
+    use crate::attacks::break_single_byte_xor;
+    use crate::ciphers::single_byte_xor;
+
+    #[test]
+    fn test_frequency_table() {
+        let table = FrequencyTable::load(&b"# a comment\nngrams 2 10\nth\t4\n\\t\\x00\t1\n\\\\\\'\t2\n"[..]).unwrap();
+        assert_eq!((table.n(), table.total()), (2, 10));
+        assert_eq!(table.count(b"th"), 4);
+        assert_eq!(table.count(b"\t\x00"), 1);
+        assert_eq!(table.count(b"\\'"), 2);
+        assert_eq!(table.count(b"he"), 0);
+        assert_eq!(table.count(b"the"), 0);
+
+        for bad in ["th\t4\n", "ngrams 9 10\n", "ngrams 2 10\nthe\t4\n", "ngrams 2 10\nth 4\n", "ngrams 2 10\n\\q\t4\n", ""].iter() {
+            let err = FrequencyTable::load(bad.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", bad);
+        }
+
+        let quadgrams = FrequencyTable::english(4).unwrap();
+        assert!(quadgrams.count(b" the") > quadgrams.count(b" tha"));
+        assert!(quadgrams.count(b" the") <= FrequencyTable::english(3).unwrap().count(b" th"));
+        assert!(FrequencyTable::english(0).is_none() && FrequencyTable::english(5).is_none());
+        assert!(NgramScorer::english(0).is_none() && NgramScorer::english(5).is_none());
+    }
+
+    #[test]
+    fn test_ngram_scorer() {
+        let scorer = NgramScorer::english(4).unwrap();
+        assert_eq!(scorer.order(), 4);
+        assert_eq!(scorer.context(), 3);
+        assert_eq!(scorer.score(b""), 0.0);
+        assert!(scorer.score(b"\x00\xff\x13").is_finite());
+
+        // the same letters in a different order. a unigram model cannot tell these apart
+        let unigram = LogLikelihoodScorer::english();
+        assert_eq!(unigram.score(b"eat on"), unigram.score(b"etaon "));
+        assert!(scorer.score(b"eat on") > scorer.score(b"etaon "));
+        assert!(scorer.score(b"the party") > scorer.score(b"teh ytrap"));
+
+        // the score is a sum over bytes, each depending on at most context() bytes before it
+        let text = b"Now that the party is jumping";
+        let split = scorer.score(&text[..12]) + scorer.score(&text[9..]) - scorer.score(&text[9..12]);
+        assert!((scorer.score(text) - split).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_train_and_save() {
+        let tables = FrequencyTable::train_up_to(&b"abcabd"[..], 3).unwrap();
+        assert_eq!(tables.iter().map(|t| (t.n(), t.total())).collect::<Vec<_>>(), [(1, 6), (2, 5), (3, 4)]);
+        assert_eq!((tables[0].count(b"a"), tables[1].count(b"ab"), tables[2].count(b"abc"), tables[2].count(b"cab")), (2, 2, 1, 1));
+        assert_eq!(tables[2], FrequencyTable::train(&b"abcabd"[..], 3).unwrap());
+        assert_eq!(FrequencyTable::train(&b"ab"[..], 3).unwrap().total(), 0);
+
+        // n-grams have to fit in a u64, and a 0-gram is no n-gram at all
+        for n in [0, MAX_NGRAM_LEN + 1] {
+            assert_eq!(FrequencyTable::train(&b"abcabd"[..], n).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(FrequencyTable::train_up_to(&b"abcabd"[..], n).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        }
+
+        let freqs = tables[0].frequencies();
+        assert_eq!((freqs[b'a' as usize], freqs[b'd' as usize], freqs[b'e' as usize]), (2.0 / 6.0, 1.0 / 6.0, 0.0));
+
+        // any bytes survive a round trip through a file
+        let binary: Vec<u8> = (0..2000u32).map(|i| (i * i % 251) as u8).collect();
+        let mut table = FrequencyTable::train(&binary[..], 2).unwrap();
+        assert!(binary.windows(2).any(|w| w[0] == b'#'));
+        let mut file: Vec<u8> = Vec::new();
+        table.save(&mut file).unwrap();
+        assert_eq!(FrequencyTable::load(&file[..]).unwrap(), table);
+
+        table.prune(8);
+        assert_eq!(table.total(), 1999);
+        assert!(table.counts.values().all(|&count| count >= 8));
+        let mut pruned_file: Vec<u8> = Vec::new();
+        table.save(&mut pruned_file).unwrap();
+        assert!(pruned_file.len() < file.len());
+        assert_eq!(FrequencyTable::load(&pruned_file[..]).unwrap(), table);
+
+        // saving writes the data files back, less their comments
+        let data = include_str!("../../data/english_bigrams.txt");
+        let mut saved: Vec<u8> = Vec::new();
+        FrequencyTable::english(2).unwrap().save(&mut saved).unwrap();
+        let uncommented: String = data.lines().skip_while(|line| line.starts_with('#')).map(|line| format!("{}\n", line)).collect();
+        assert_eq!(String::from_utf8(saved).unwrap(), uncommented);
+    }
+
+    // tables trained on a kind of plaintext break single-byte XOR on short pieces of it
+    // more often than the English ones. each corpus in data/corpora has a sample of the
+    // same kind that is not part of it: French prose, Rust source, JSON records and
+    // x86-64 machine code. French is close enough to English that the difference is small
+    #[test]
+    fn test_trained_scorers() {
+        let workloads: [(&str, &[u8], &[u8]); 4] = [
+            ("French", include_bytes!("../../data/corpora/french.txt"), include_bytes!("../../data/corpora/french_sample.txt")),
+            ("Rust", include_bytes!("../../data/corpora/rust.txt"), include_bytes!("../../data/corpora/rust_sample.txt")),
+            ("JSON", include_bytes!("../../data/corpora/json.txt"), include_bytes!("../../data/corpora/json_sample.txt")),
+            ("x86-64", include_bytes!("../../data/corpora/x86_64.bin"), include_bytes!("../../data/corpora/x86_64_sample.bin")),
+        ];
+        let english = NgramScorer::english(3).unwrap();
+        for &(name, corpus, plaintext) in workloads.iter() {
+            let trained = NgramScorer::new(&FrequencyTable::train_up_to(corpus, 3).unwrap());
+
+            // short pieces with keys that vary from piece to piece
+            let hits = |scorer: &NgramScorer| -> usize {
+                return plaintext.chunks_exact(6).enumerate()
+                    .filter(|&(i, chunk)| {
+                        let key = (i * 151 + 7) as u8;
+                        break_single_byte_xor(&single_byte_xor(chunk, key), scorer).0 == key
+                    })
+                    .count();
+            };
+            let (trained_hits, english_hits) = (hits(&trained), hits(&english));
+            let total = plaintext.len() / 6;
+            assert!(trained_hits > english_hits && trained_hits * 10 >= total * 9,
+                    "{}: trained {}/{}, English {}", name, trained_hits, total, english_hits);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::analysis::PlaintextScorer;
+use crate::ciphers::single_byte_xor;
+use crate::xor::hamming_distance;
+
+// the key size that break_repeating_key_xor tries up to
+pub const MAX_KEY_SIZE: usize = 50;
+
+// the single-byte XOR key whose plaintext scores highest with scorer, and that score
+pub fn break_single_byte_xor(ciphertext: &[u8], scorer: &dyn PlaintextScorer) -> (u8, f64) {
+    let mut best_score: f64 = f64::NEG_INFINITY;
+    let mut best_key: u8 = 0;
+    for k in 0u8..=255 {
+        let score: f64 = scorer.score(&single_byte_xor(ciphertext, k));
+
+        if score > best_score {
+            best_score = score;
+            best_key = k;
+        }
+    }
+
+    return (best_key, best_score);
+}
+
+// every single-byte XOR key with the score of its plaintext, best first
+pub fn rank_single_byte_xor(ciphertext: &[u8], scorer: &dyn PlaintextScorer) -> Vec<(u8, f64)> {
+    let mut ranked: Vec<(u8, f64)> = (0u8..=255).map(|k| (k, scorer.score(&single_byte_xor(ciphertext, k)))).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    return ranked;
+}
+
+// the ciphertext among candidates that was most likely encrypted with
+// single-byte XOR: its index, key and score. None if there are no candidates
+pub fn detect_single_byte_xor<T: AsRef<[u8]>>(candidates: &[T], scorer: &dyn PlaintextScorer) -> Option<(usize, u8, f64)> {
+    let mut best: Option<(usize, u8, f64)> = None;
+    for (i, candidate) in candidates.iter().enumerate() {
+        let (key, score) = break_single_byte_xor(candidate.as_ref(), scorer);
+
+        if best.is_none_or(|(_, _, best_score)| score > best_score) {
+            best = Some((i, key, score));
+        }
+    }
+
+    return best;
+}
+
+// calculate the average Hamming distance given a specific window size
+fn window_hamming_distance(s: &[u8], size: usize) -> f64 {
+    let mut i: usize = 0;
+    let mut d: u64 = 0;
+    while (i+2)*size < s.len() {
+        let frame1 = &s[(i*size) .. (i+1)*size];
+        let frame2 = &s[(i+1)*size .. (i+2)*size];
+        d += hamming_distance(frame1, frame2).expect("frames are the same size");
+        i += 1;
+    }
+
+    return (d as f64) / (s.len() as f64);
+}
+
+// the repeating XOR key size, up to max_key_size, whose adjacent
+// windows of ciphertext are closest together
+pub fn guess_key_size(ciphertext: &[u8], max_key_size: usize) -> usize {
+    let mut best_d = window_hamming_distance(ciphertext, 1);
+    let mut best_key_size: usize = 1;
+    for window_size in 2 ..= max_key_size {
+        let d = window_hamming_distance(ciphertext, window_size);
+        if d < best_d {
+            best_d = d;
+            best_key_size = window_size;
+        }
+    }
+
+    return best_key_size;
+}
+
+// recover the key of a repeating-key XOR ciphertext, using scorer to recognize the plaintext
+pub fn break_repeating_key_xor(ciphertext: &[u8], scorer: &dyn PlaintextScorer) -> Vec<u8> {
+    let key_size = guess_key_size(ciphertext, MAX_KEY_SIZE);
+    return recover_repeating_key(ciphertext, key_size, scorer);
+}
+
+// recover a repeating XOR key of a known size. a key size of 0 gets an empty key
+pub fn recover_repeating_key(ciphertext: &[u8], key_size: usize, scorer: &dyn PlaintextScorer) -> Vec<u8> {
+    if key_size == 0 {
+        return Vec::new();
+    }
+
+    // arrange blocks to find individual characters of key
+    // e.g. if key is length 3, arrange bytes 1, 4, 7, ... into one block;
+    // arrange bytes 2, 5, 8, ... into another block; and so on
+    let mut blocks = vec![Vec::new(); key_size];
+    for (i, b) in ciphertext.iter().enumerate() {
+        blocks[i % key_size].push(*b);
+    }
+
+    if scorer.context() == 0 {
+        return blocks.iter().map(|block| break_single_byte_xor(block, scorer).0).collect();
+    }
+
+    // the bytes of a block are not next to each other in the plaintext, so a scorer
+    // that looks at neighbouring bytes only sees noise in them. the best few bytes
+    // for each block are still likely to include the right one, so choose
+    // between those again with the whole plaintext in view
+    let candidates: Vec<Vec<u8>> = blocks.iter()
+        .map(|block| rank_single_byte_xor(block, scorer).iter().take(REFINE_CANDIDATES).map(|&(k, _)| k).collect())
+        .collect();
+    return refine_repeating_key(ciphertext, &candidates, scorer);
+}
+
+// how many of the best bytes for each block refine_repeating_key chooses between
+const REFINE_CANDIDATES: usize = 8;
+
+// passes refine_repeating_key makes at most, in case two key bytes keep trading places
+const MAX_REFINE_PASSES: usize = 8;
+
+// scorer's score for plaintext, up to an amount that does not depend on key byte j
+// of a key_size-byte repeating key. with a scorer whose terms each depend on
+// context() bytes, key byte j only changes the terms of the context()+1 bytes starting
+// at each position it decrypts. when the key is longer than the context, the window
+// around such a position scores exactly those terms, plus those of the context() bytes
+// before it with less context than in the whole plaintext. those bytes and what they
+// see lie strictly between two positions key byte j decrypts, and they are all that
+// windows share when they overlap (key_size < 2*context()+1), so they add the same
+// amount whatever key byte j is
+fn key_byte_score(plaintext: &[u8], j: usize, key_size: usize, scorer: &dyn PlaintextScorer) -> f64 {
+    let ctx = scorer.context();
+    if key_size <= ctx {
+        return scorer.score(plaintext);
+    }
+
+    return (j..plaintext.len()).step_by(key_size)
+        .map(|p| scorer.score(&plaintext[p.saturating_sub(ctx) .. (p+ctx+1).min(plaintext.len())]))
+        .sum();
+}
+
+// starting from the first candidate for each key byte, choose each byte in turn as
+// the candidate that makes the whole plaintext score highest, until no byte changes
+fn refine_repeating_key(ciphertext: &[u8], candidates: &[Vec<u8>], scorer: &dyn PlaintextScorer) -> Vec<u8> {
+    let key_size = candidates.len();
+    let mut key: Vec<u8> = candidates.iter().map(|c| c[0]).collect();
+    let mut plaintext: Vec<u8> = ciphertext.iter().enumerate().map(|(i, c)| c ^ key[i % key_size]).collect();
+
+    for _ in 0..MAX_REFINE_PASSES {
+        let mut changed = false;
+        for j in 0..key_size {
+            let mut best_score: f64 = f64::NEG_INFINITY;
+            let mut best_byte: u8 = key[j];
+            for &k in candidates[j].iter() {
+                for p in (j..plaintext.len()).step_by(key_size) {
+                    plaintext[p] = ciphertext[p] ^ k;
+                }
+
+                let score: f64 = key_byte_score(&plaintext, j, key_size, scorer);
+                if score > best_score {
+                    best_score = score;
+                    best_byte = k;
+                }
+            }
+
+            for p in (j..plaintext.len()).step_by(key_size) {
+                plaintext[p] = ciphertext[p] ^ best_byte;
+            }
+            changed |= best_byte != key[j];
+            key[j] = best_byte;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    return key;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::analysis::ngram::NgramScorer;
+
+    #[test]
+    fn test_key_byte_score() {
+        let scorer = NgramScorer::english(4).unwrap();
+        let plaintext = b"I'm back and I'm ringin' the bell, a rockin' on the mike while the fly girls yell";
+
+        // every key size from one that windows overlap in to one they do not
+        for key_size in 1..=10 {
+            for j in 0..key_size {
+                let mut changed = plaintext.to_vec();
+                for p in (j..changed.len()).step_by(key_size) {
+                    changed[p] ^= 0x20;
+                }
+
+                let full = scorer.score(&changed) - scorer.score(plaintext);
+                let windowed = key_byte_score(&changed, j, key_size, &scorer) - key_byte_score(plaintext, j, key_size, &scorer);
+                assert!((full - windowed).abs() < 1e-9, "key size {}, byte {}: {} != {}", key_size, j, full, windowed);
+            }
+        }
+
+        assert!(recover_repeating_key(b"ciphertext", 0, &scorer).is_empty());
+    }
+}
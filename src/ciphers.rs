@@ -0,0 +1,12 @@
+use crate::xor::xor_with_key;
+
+// the XOR ciphers of set 1. XOR is its own inverse, so each function
+// both encrypts and decrypts
+
+// XOR bytes with key repeated end to end ("ICE" encrypts "Burning" with "ICEICEI")
+pub use crate::xor::xor_with_key as repeating_key_xor;
+
+// XOR every byte with the same key byte: a repeating key of one byte
+pub fn single_byte_xor(bytes: &[u8], key: u8) -> Vec<u8> {
+    return xor_with_key(bytes, &[key]).expect("a one-byte key is not empty");
+}
@@ -1,72 +1,387 @@
-use std::{ops::Deref, fmt};
+use std::fmt;
+use std::ops::{BitXor, BitXorAssign, Deref, DerefMut};
+use std::str::FromStr;
+
+pub mod codec;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod swar;
+pub mod stream;
+pub mod buf;
+pub mod ct;
+pub mod base32;
+pub mod base58;
+pub mod ascii85;
+pub mod pem;
 
 const BASE64_PADDING_VAL: u8 = 255;
 
+// reasons an encoding or decoding can fail.
+// offsets are byte positions into the input that was being converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingError {
+    // input length is not a multiple of the encoding's block size
+    InvalidLength { len: usize, multiple: usize },
+
+    // character outside the encoding's alphabet (or value too large to encode)
+    InvalidDigit { byte: u8, offset: usize },
+
+    // '=' anywhere other than the tail of the final base64 or base32 quantum
+    InvalidPadding { offset: usize },
+
+    // base64 whose final character has non-zero bits past the end of the data,
+    // which strict decoding rejects since it would decode the same as a zeroed one
+    NonCanonical { byte: u8, offset: usize },
+
+    // base85 group that decodes to more than 32 bits
+    ValueOverflow { offset: usize },
+
+    // decoded data does not match its embedded checksum (Base58Check)
+    BadChecksum,
+
+    // xor_bytes, xor_assign or xor_into was given buffers of different lengths
+    LengthMismatch { left: usize, right: usize },
+
+    // xor_with_key was given a key with no bytes to repeat
+    EmptyKey,
+
+    // hexdump line (starting at offset) with no address, or one that goes backwards
+    InvalidAddress { offset: usize },
+
+    // PEM "-----BEGIN" or "-----END" line (at offset) that is malformed or has no partner
+    InvalidBoundary { offset: usize },
+}
+
+impl EncodingError {
+    // hex_val and friends only see a single character, so they report offset 0;
+    // callers that know where the character came from move the error there
+    pub fn at(self, offset: usize) -> EncodingError {
+        match self {
+            EncodingError::InvalidDigit { byte, .. } =>
+                EncodingError::InvalidDigit { byte, offset },
+
+            EncodingError::InvalidPadding { .. } =>
+                EncodingError::InvalidPadding { offset },
+
+            EncodingError::NonCanonical { byte, .. } =>
+                EncodingError::NonCanonical { byte, offset },
+
+            EncodingError::ValueOverflow { .. } =>
+                EncodingError::ValueOverflow { offset },
+
+            other => other,
+        }
+    }
+
+    // position of the offending character, for errors that have one
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            EncodingError::InvalidDigit { offset, .. } => Some(offset),
+            EncodingError::InvalidPadding { offset } => Some(offset),
+            EncodingError::NonCanonical { offset, .. } => Some(offset),
+            EncodingError::ValueOverflow { offset } => Some(offset),
+            EncodingError::InvalidAddress { offset } => Some(offset),
+            EncodingError::InvalidBoundary { offset } => Some(offset),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodingError::InvalidLength { len, multiple } =>
+                write!(f, "input length {} is not a multiple of {}", len, multiple),
+
+            EncodingError::InvalidDigit { byte, offset } =>
+                write!(f, "invalid digit 0x{:02x} at offset {}", byte, offset),
+
+            EncodingError::InvalidPadding { offset } =>
+                write!(f, "misplaced padding at offset {}", offset),
+
+            EncodingError::NonCanonical { byte, offset } =>
+                write!(f, "non-canonical final digit 0x{:02x} at offset {}", byte, offset),
+
+            EncodingError::ValueOverflow { offset } =>
+                write!(f, "group starting at offset {} does not fit in 32 bits", offset),
+
+            EncodingError::BadChecksum =>
+                write!(f, "checksum mismatch"),
+
+            EncodingError::LengthMismatch { left, right } =>
+                write!(f, "cannot XOR buffers of lengths {} and {}", left, right),
+
+            EncodingError::EmptyKey =>
+                write!(f, "cannot XOR with an empty key"),
+
+            EncodingError::InvalidAddress { offset } =>
+                write!(f, "missing or out-of-order address in hexdump line at offset {}", offset),
+
+            EncodingError::InvalidBoundary { offset } =>
+                write!(f, "malformed or unmatched PEM boundary at offset {}", offset),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+// options for hex_encode_with and hex_decode_with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexConfig {
+    // encode with A-F instead of a-f. decoding accepts both cases regardless
+    pub uppercase: bool,
+
+    // when decoding, skip "0x" prefixes, ':' and whitespace between bytes,
+    // so dumps like "0xDE 0xAD" or "de:ad:be:ef" can be pasted in as-is
+    pub skip_separators: bool,
+}
+
+impl HexConfig {
+    pub const LOWER: HexConfig = HexConfig { uppercase: false, skip_separators: false };
+    pub const UPPER: HexConfig = HexConfig { uppercase: true, skip_separators: false };
+    pub const LENIENT: HexConfig = HexConfig { uppercase: false, skip_separators: true };
+}
+
+// base64 alphabets differ only in the characters used for sextets 62 and 63
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64Alphabet {
+    Standard, // '+' and '/'
+    UrlSafe, // '-' and '_' (RFC 4648 section 5, used by JWTs)
+}
+
+// how forgiving base64_decode_with is. encoding ignores the mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64Mode {
+    // padding must follow the config, and unused bits in the final character are ignored
+    Normal,
+
+    // as Normal, but unused bits in the final character must be zero, so that every
+    // byte string has exactly one accepted encoding. for signatures and tokens
+    Strict,
+
+    // skip whitespace anywhere and accept input with or without padding. for pasted data
+    Lenient,
+}
+
+// options for base64_encode_with and base64_decode_with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64Config {
+    pub alphabet: Base64Alphabet,
+
+    // emit '=' padding when encoding and require it when decoding.
+    // without padding, '=' in the input is rejected
+    pub pad: bool,
+
+    // wrap encoded output with "\r\n" every line_wrap characters.
+    // if set, decoding skips whitespace anywhere in the input
+    pub line_wrap: Option<usize>,
+
+    pub mode: Base64Mode,
+}
+
+impl Base64Config {
+    pub const STANDARD: Base64Config =
+        Base64Config { alphabet: Base64Alphabet::Standard, pad: true, line_wrap: None, mode: Base64Mode::Normal };
+
+    pub const STANDARD_NO_PAD: Base64Config =
+        Base64Config { pad: false, ..Base64Config::STANDARD };
+
+    pub const URL_SAFE: Base64Config =
+        Base64Config { alphabet: Base64Alphabet::UrlSafe, ..Base64Config::STANDARD };
+
+    pub const URL_SAFE_NO_PAD: Base64Config =
+        Base64Config { alphabet: Base64Alphabet::UrlSafe, pad: false, ..Base64Config::STANDARD };
+
+    // RFC 2045 content-transfer-encoding, as used in PEM files and 6.txt
+    pub const MIME: Base64Config =
+        Base64Config { line_wrap: Some(76), ..Base64Config::STANDARD };
+
+    pub const STRICT: Base64Config =
+        Base64Config { mode: Base64Mode::Strict, ..Base64Config::STANDARD };
+
+    pub const LENIENT: Base64Config =
+        Base64Config { mode: Base64Mode::Lenient, ..Base64Config::STANDARD };
+
+    // whether decoding ignores whitespace
+    pub fn skips_whitespace(&self) -> bool {
+        return self.line_wrap.is_some() || self.mode == Base64Mode::Lenient;
+    }
+
+    // whether decoding insists on '=' padding
+    pub fn requires_padding(&self) -> bool {
+        return self.pad && self.mode != Base64Mode::Lenient;
+    }
+
+    // whether decoding accepts '=' padding at all
+    pub fn allows_padding(&self) -> bool {
+        return self.pad || self.mode == Base64Mode::Lenient;
+    }
+}
+
 // XOR two byte vectors
-pub fn xor_bytes(buf1: &[u8], buf2: &[u8]) -> Vec<u8> {
+pub fn xor_bytes(buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    let mut result: Vec<u8> = buf1.to_vec();
+    xor_assign(&mut result, buf2)?;
+    return Ok(result);
+}
+
+// XOR src into dst in place, without allocating
+pub fn xor_assign(dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> {
+    if dst.len() != src.len() {
+        return Err(EncodingError::LengthMismatch { left: dst.len(), right: src.len() });
+    }
+
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+
+    return Ok(());
+}
+
+// write buf1 XOR buf2 into out, without allocating. all three must be the same length
+pub fn xor_into(out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> {
     if buf1.len() != buf2.len() {
-        panic!("xor_bytes: cannot XOR two byte vectors with different lengths");
+        return Err(EncodingError::LengthMismatch { left: buf1.len(), right: buf2.len() });
+    }
+    if out.len() != buf1.len() {
+        return Err(EncodingError::LengthMismatch { left: out.len(), right: buf1.len() });
     }
 
-    let mut result: Vec<u8> = Vec::new();
-    for i in 0..buf1.len() {
-        result.push(buf1[i] ^ buf2[i]);
+    for ((o, b1), b2) in out.iter_mut().zip(buf1).zip(buf2) {
+        *o = b1 ^ b2;
     }
 
-    return result;
+    return Ok(());
 }
 
-pub fn hex_char(b: u8) -> char {
-    let val =
-        if b <= 9 {
-            b + 48
+// XOR bytes with key repeated end to end (set 1 challenge 5's "repeating-key XOR").
+// a 1-byte key is the single-byte XOR of challenge 3
+pub fn xor_with_key(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    if key.is_empty() {
+        return Err(EncodingError::EmptyKey);
+    }
 
-        } else if 10 <= b && b <= 15 {
-            b + 87
+    let mut result: Vec<u8> = bytes.to_vec();
+    for chunk in result.chunks_mut(key.len()) {
+        for (b, k) in chunk.iter_mut().zip(key) {
+            *b ^= k;
+        }
+    }
 
-        } else {
-            panic!("invalid hex-encoded byte {}", b);
-        };
-    val as char
+    return Ok(result);
 }
 
-pub fn hex_val(c: char) -> u8 {
-    let c_ascii = c as u8;
-    return if 48 <= c_ascii && c_ascii <= 57 {
-        c_ascii - 48
+// XOR bytes in place with a keystream, e.g. the output of a stream cipher.
+// if the keystream ends early, the bytes past its end are left as they are
+pub fn xor_with_keystream(bytes: &mut [u8], keystream: impl Iterator<Item=u8>) {
+    for (b, k) in bytes.iter_mut().zip(keystream) {
+        *b ^= k;
+    }
+}
 
-    } else if 97 <= c_ascii && c_ascii <= 102 {
-        c_ascii - 97 + 10
+// the error for a char that is not a digit of any alphabet here. callers that scan
+// bytes pass them as the chars U+0000..U+00FF, which map back to the same byte;
+// any other char is reported by the first byte of its UTF-8 encoding
+pub(crate) fn invalid_digit(c: char) -> EncodingError {
+    let byte = if (c as u32) < 0x100 { c as u8 } else { c.encode_utf8(&mut [0; 4]).as_bytes()[0] };
+    return EncodingError::InvalidDigit { byte, offset: 0 };
+}
 
-    } else {
-        panic!("invalid hex-encoded byte {}", c);
+pub fn hex_char(b: u8) -> Result<char, EncodingError> {
+    let val = match b {
+        0..=9 => b + 48,
+        10..=15 => b + 87,
+        _ => return Err(EncodingError::InvalidDigit { byte: b, offset: 0 }),
+    };
+    return Ok(val as char);
+}
+
+pub fn hex_val(c: char) -> Result<u8, EncodingError> {
+    // checked before the cast, which would keep only the low byte of the char
+    if !c.is_ascii() {
+        return Err(invalid_digit(c));
     }
+
+    let c_ascii = c as u8;
+    return match c_ascii {
+        48..=57 => Ok(c_ascii - 48), // 0-9
+        65..=70 => Ok(c_ascii - 65 + 10), // A-F
+        97..=102 => Ok(c_ascii - 97 + 10), // a-f
+        _ => Err(EncodingError::InvalidDigit { byte: c_ascii, offset: 0 }),
+    };
 }
 
-pub fn hex_to_ascii_str(str: &str) -> String {
-    if str.len() % 2 != 0 {
-        panic!("hex string length must be divisible by 2 to convert to ascii");
+// decode a hex string into raw bytes
+pub fn hex_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    if !str.len().is_multiple_of(2) {
+        return Err(EncodingError::InvalidLength { len: str.len(), multiple: 2 });
     }
 
     let str_bytes = str.as_bytes();
-    let mut ascii_string: String = String::new();
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/2);
     for i in 0..str_bytes.len()/2 {
         let b: usize = i * 2;
-        let hex_val1 = hex_val(str_bytes[b] as char);
-        let hex_val2 = hex_val(str_bytes[b+1] as char);
-        let ascii_byte: u8 = ((hex_val1 << 4) & 0b11110000) | hex_val2;
-        ascii_string.push(ascii_byte as char);
+        let hex_val1 = hex_val(str_bytes[b] as char).map_err(|e| e.at(b))?;
+        let hex_val2 = hex_val(str_bytes[b+1] as char).map_err(|e| e.at(b+1))?;
+        bytes.push(((hex_val1 << 4) & 0b11110000) | hex_val2);
     }
 
-    return ascii_string;
+    return Ok(bytes);
 }
 
-pub fn ascii_to_hex_str(str: &str) -> String {
+// decode hex, optionally skipping the separators allowed by config.
+// separators may only appear between bytes, never between the two digits of one byte
+pub fn hex_decode_with(str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> {
+    if !config.skip_separators {
+        return hex_decode(str);
+    }
+
     let str_bytes = str.as_bytes();
-    let mut hex_string: String = String::new();
-    for i in 0..str_bytes.len() {
-        let hex_char_upper: char = hex_char((str_bytes[i] & 0b11110000) >> 4);
-        let hex_char_lower: char = hex_char(str_bytes[i] & 0b00001111);
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/2);
+    let mut i: usize = 0;
+    while i < str_bytes.len() {
+        let c = str_bytes[i];
+        if c == b':' || c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == b'0' && i+1 < str_bytes.len() && (str_bytes[i+1] == b'x' || str_bytes[i+1] == b'X') {
+            i += 2;
+            continue;
+        }
+
+        // a lone trailing digit has no partner to form a byte with. len counts
+        // the digits of the payload only, not those of "0x" prefixes
+        if i+1 >= str_bytes.len() {
+            return Err(EncodingError::InvalidLength { len: bytes.len()*2 + 1, multiple: 2 });
+        }
+
+        let hex_val1 = hex_val(c as char).map_err(|e| e.at(i))?;
+        let hex_val2 = hex_val(str_bytes[i+1] as char).map_err(|e| e.at(i+1))?;
+        bytes.push(((hex_val1 << 4) & 0b11110000) | hex_val2);
+        i += 2;
+    }
+
+    return Ok(bytes);
+}
+
+// encode raw bytes as a lowercase hex string
+pub fn hex_encode(bytes: &[u8]) -> String {
+    return hex_encode_with(bytes, &HexConfig::LOWER);
+}
+
+// encode raw bytes as hex, in the digit case chosen by config
+pub fn hex_encode_with(bytes: &[u8], config: &HexConfig) -> String {
+    let mut hex_string: String = String::with_capacity(bytes.len()*2);
+    for byte in bytes {
+        // a nibble is always a valid hex digit
+        let mut hex_char_upper: char = hex_char((byte & 0b11110000) >> 4).unwrap();
+        let mut hex_char_lower: char = hex_char(byte & 0b00001111).unwrap();
+        if config.uppercase {
+            hex_char_upper = hex_char_upper.to_ascii_uppercase();
+            hex_char_lower = hex_char_lower.to_ascii_uppercase();
+        }
         hex_string.push(hex_char_upper);
         hex_string.push(hex_char_lower);
     }
@@ -74,124 +389,466 @@ pub fn ascii_to_hex_str(str: &str) -> String {
     return hex_string;
 }
 
-pub fn base64_char(b: u8) -> char {
-    let val =
-        if b <= 25 { // A-Z
-            b + 65
+// decode hex into a string with one char per byte.
+// bytes >= 0x80 become the chars U+0080..U+00FF, so use hex_decode for binary data
+pub fn hex_to_ascii_str(str: &str) -> Result<String, EncodingError> {
+    return Ok(hex_decode(str)?.into_iter().map(|b| b as char).collect());
+}
 
-        } else if 26 <= b && b <= 51 { // a-z
-            b + 71
+pub fn ascii_to_hex_str(str: &str) -> String {
+    return hex_encode(str.as_bytes());
+}
 
-        } else if 52 <= b && b <= 61 { // 0-9
-            b - 4 
+pub fn base64_char(b: u8) -> Result<char, EncodingError> {
+    let val = match b {
+        0..=25 => b + 65, // A-Z
+        26..=51 => b + 71, // a-z
+        52..=61 => b - 4, // 0-9
+        62 => 43, // +
+        63 => 47, // /
+        _ => return Err(EncodingError::InvalidDigit { byte: b, offset: 0 }),
+    };
+    return Ok(val as char);
+}
+
+pub fn base64_val(c: char) -> Result<u8, EncodingError> {
+    if !c.is_ascii() {
+        return Err(invalid_digit(c));
+    }
 
-        } else if b == 62 { // +
-            43
+    let c_ascii = c as u8;
+    let val = match c_ascii {
+        65..=90 => c_ascii - 65, // A-Z
+        97..=122 => c_ascii - 97 + 26, // a-z
+        48..=57 => c_ascii - 48 + 52, // 0-9
+        43 => 62, // +
+        47 => 63, // /
+        61 => BASE64_PADDING_VAL, // = (padding)
+        _ => return Err(EncodingError::InvalidDigit { byte: c_ascii, offset: 0 }),
+    };
+    return Ok(val);
+}
 
-        } else if b == 63 { // /
-            47
+// base64_char for an arbitrary alphabet
+pub fn base64_char_with(b: u8, alphabet: Base64Alphabet) -> Result<char, EncodingError> {
+    return match (base64_char(b)?, alphabet) {
+        ('+', Base64Alphabet::UrlSafe) => Ok('-'),
+        ('/', Base64Alphabet::UrlSafe) => Ok('_'),
+        (c, _) => Ok(c),
+    };
+}
 
+// base64_val for an arbitrary alphabet; characters of the other alphabet are rejected
+pub fn base64_val_with(c: char, alphabet: Base64Alphabet) -> Result<u8, EncodingError> {
+    return match (c, alphabet) {
+        ('-', Base64Alphabet::UrlSafe) => Ok(62),
+        ('_', Base64Alphabet::UrlSafe) => Ok(63),
+        ('+', Base64Alphabet::UrlSafe) | ('/', Base64Alphabet::UrlSafe) =>
+            Err(EncodingError::InvalidDigit { byte: c as u8, offset: 0 }),
+        _ => base64_val(c),
+    };
+}
+
+// re-encode a hex string as padded base64
+pub fn hex_to_base64(str: &str) -> Result<String, EncodingError> {
+    return Ok(bytes_to_base64(&hex_decode(str)?));
+}
+
+// decode a padded base64 string into raw bytes
+pub fn base64_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    return base64_decode_with(str, &Base64Config::STANDARD);
+}
+
+// decode base64 in the variant described by config
+pub fn base64_decode_with(str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+
+    // offsets of the characters that carry data, so that errors
+    // still point into the original input after whitespace is skipped
+    let positions: Vec<usize> =
+        if config.skips_whitespace() {
+            (0..str_bytes.len()).filter(|&i| !str_bytes[i].is_ascii_whitespace()).collect()
         } else {
-            panic!("invalid base64-encoded byte {}", b)
+            (0..str_bytes.len()).collect()
         };
-    val as char
-}
 
-pub fn base64_val(c: char) -> u8 {
-    let c_ascii = c as u8;
-    let val =
-        if 65 <= c_ascii && c_ascii <= 90 { // A-Z
-            c_ascii - 65
+    // unpadded input may end in a 2 or 3 character quantum, but never a single character
+    let len_ok =
+        if config.requires_padding() {
+            positions.len().is_multiple_of(4)
+        } else {
+            positions.len() % 4 != 1
+        };
+    if !len_ok {
+        return Err(EncodingError::InvalidLength { len: positions.len(), multiple: 4 });
+    }
 
-        } else if 97 <= c_ascii && c_ascii <= 122 { // a-z
-            c_ascii - 97 + 26
+    let mut bytes: Vec<u8> = Vec::with_capacity(positions.len()/4*3 + 2);
+    for (i, quantum) in positions.chunks(4).enumerate() {
+        let last_quantum = (i+1)*4 >= positions.len();
 
-        } else if 48 <= c_ascii && c_ascii <= 57 { // 0-9
-            c_ascii - 48 + 52
+        // a short final quantum of unpadded input decodes as if it were padded
+        let mut vals = [BASE64_PADDING_VAL; 4];
+        for (j, &b) in quantum.iter().enumerate() {
+            let c = str_bytes[b] as char;
+            if c == '=' && !config.allows_padding() {
+                return Err(EncodingError::InvalidPadding { offset: b });
+            }
+            vals[j] = base64_val_with(c, config.alphabet).map_err(|e| e.at(b))?;
+        }
+        let [val1, val2, val3, val4] = vals;
 
-        } else if c_ascii == 43 { // +
-            62
+        // padding may only fill the last one or two characters of the final quantum,
+        // and "x=y=" style gaps are not allowed either
+        if val1 == BASE64_PADDING_VAL || val2 == BASE64_PADDING_VAL {
+            let offset = if val1 == BASE64_PADDING_VAL { quantum[0] } else { quantum[1] };
+            return Err(EncodingError::InvalidPadding { offset });
+        }
+
+        if val3 == BASE64_PADDING_VAL && val4 != BASE64_PADDING_VAL {
+            return Err(EncodingError::InvalidPadding { offset: quantum[2] });
+        }
+
+        if !last_quantum && val4 == BASE64_PADDING_VAL {
+            let offset = if val3 == BASE64_PADDING_VAL { quantum[2] } else { quantum[3] };
+            return Err(EncodingError::InvalidPadding { offset });
+        }
+
+        // a short final quantum only uses the top 2 or 4 bits of its last character
+        if config.mode == Base64Mode::Strict {
+            let (last, unused_bits) =
+                if val3 == BASE64_PADDING_VAL { (1, val2 & 0b00001111) }
+                else if val4 == BASE64_PADDING_VAL { (2, val3 & 0b00000011) }
+                else { (3, 0) };
+
+            if unused_bits != 0 {
+                let offset = quantum[last];
+                return Err(EncodingError::NonCanonical { byte: str_bytes[offset], offset });
+            }
+        }
+
+        let byte1: u8 = ((val1 << 2) & 0b11111100) | ((val2 >> 4) & 0b00000011);
+        let byte2: u8 = ((val2 << 4) & 0b11110000) | ((val3 >> 2) & 0b00001111);
+        let byte3: u8 = ((val3 << 6) & 0b11000000) | (val4  & 0b00111111);
+
+        bytes.push(byte1);
+
+        if val4 == BASE64_PADDING_VAL {
+            // one '=' padding, only decode two bytes
+            if val3 != BASE64_PADDING_VAL {
+                bytes.push(byte2);
+            }
+
+        } else { // no '=' padding, decode all three possible bytes
+            bytes.push(byte2);
+            bytes.push(byte3);
+        }
+    }
+
+    return Ok(bytes);
+}
 
-        } else if c_ascii == 47 { // /
-            63
+// encode raw bytes as base64. inputs whose length is not a multiple of 3
+// end in a short quantum, which is padded with "==" (1 byte left) or "=" (2 bytes left)
+pub fn bytes_to_base64(bytes: &[u8]) -> String {
+    let mut b64_str: String = String::with_capacity(bytes.len().div_ceil(3)*4);
+    for chunk in bytes.chunks(3) {
+        let byte1 = chunk[0];
+        let byte2 = chunk.get(1).copied().unwrap_or(0);
+        let byte3 = chunk.get(2).copied().unwrap_or(0);
 
-        } else if c_ascii == 61 { // = (padding)
-            BASE64_PADDING_VAL
+        // every sextet is < 64, so base64_char cannot fail
+        b64_str.push(base64_char(byte1 >> 2).unwrap());
+        b64_str.push(base64_char(((byte1 & 0b00000011) << 4) | (byte2 >> 4)).unwrap());
 
+        if chunk.len() > 1 {
+            b64_str.push(base64_char(((byte2 & 0b00001111) << 2) | (byte3 >> 6)).unwrap());
         } else {
-            panic!("invalid base64-encoded byte {}", c_ascii)
+            b64_str.push('=');
+        }
+
+        if chunk.len() > 2 {
+            b64_str.push(base64_char(byte3 & 0b00111111).unwrap());
+        } else {
+            b64_str.push('=');
+        }
+    }
+
+    return b64_str;
+}
+
+// encode raw bytes as base64; the byte-oriented twin of base64_decode
+pub fn base64_encode(bytes: &[u8]) -> String {
+    return bytes_to_base64(bytes);
+}
+
+// encode raw bytes as base64 in the variant described by config
+pub fn base64_encode_with(bytes: &[u8], config: &Base64Config) -> String {
+    let mut b64_str = bytes_to_base64(bytes);
+
+    if config.alphabet == Base64Alphabet::UrlSafe {
+        b64_str = b64_str.replace('+', "-").replace('/', "_");
+    }
+
+    if !config.pad {
+        b64_str.truncate(b64_str.trim_end_matches('=').len());
+    }
+
+    return match config.line_wrap {
+        Some(width) if width > 0 => {
+            let lines: Vec<&str> = b64_str.as_bytes()
+                .chunks(width)
+                .map(|line| std::str::from_utf8(line).unwrap())
+                .collect();
+            lines.join("\r\n")
+        },
+
+        _ => b64_str,
+    };
+}
+
+// decode base64 into a string with one char per byte; see hex_to_ascii_str
+pub fn base64_to_ascii_str(str: &str) -> Result<String, EncodingError> {
+    return Ok(base64_decode(str)?.into_iter().map(|b| b as char).collect());
+}
+
+// bytes per line of hexdump
+const HEXDUMP_WIDTH: usize = 16;
+
+// format bytes the way xxd does: an address, the bytes in hex in groups of two,
+// and the printable ones in an ASCII column. every line ends in '\n'
+pub fn hexdump(bytes: &[u8]) -> String {
+    let line_len = 10 + HEXDUMP_WIDTH/2*5 + 2 + HEXDUMP_WIDTH + 1;
+    let mut dump: String = String::with_capacity(bytes.len().div_ceil(HEXDUMP_WIDTH) * line_len);
+    for (i, line) in bytes.chunks(HEXDUMP_WIDTH).enumerate() {
+        dump.push_str(&format!("{:08x}:", i * HEXDUMP_WIDTH));
+        for j in 0..HEXDUMP_WIDTH {
+            if j % 2 == 0 {
+                dump.push(' ');
+            }
+
+            // a short last line is padded so that its ASCII column lines up
+            match line.get(j) {
+                Some(&b) => {
+                    dump.push(hex_char(b >> 4).unwrap());
+                    dump.push(hex_char(b & 0b00001111).unwrap());
+                },
+                None => dump.push_str("  "),
+            }
+        }
+
+        dump.push_str("  ");
+        for &b in line {
+            dump.push(if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' });
+        }
+        dump.push('\n');
+    }
+
+    return dump;
+}
+
+// parse a dump back into bytes, like xxd -r. each line is an address, a ':',
+// and hex bytes up to the first double space (where the ASCII column starts,
+// if there is one). a line whose address skips ahead leaves a run of zeros.
+// the address comes from the input, so it may only skip up to one line ahead,
+// unless the line before is a '*', which xxd -a prints for a run of zero lines
+pub fn hexdump_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut line_start: usize = 0;
+    let mut after_run = false;
+    for line in str.split('\n') {
+        let b = line_start;
+        line_start += line.len() + 1;
+
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.trim() == "*" {
+            after_run = true;
+            continue;
+        }
+
+        let colon = match line.find(':') {
+            Some(colon) => colon,
+            None => return Err(EncodingError::InvalidAddress { offset: b }),
         };
-    return val
+
+        // bytes, not chars, so that offsets count bytes like everywhere else
+        let mut address: usize = 0;
+        for (i, &c) in line.as_bytes()[..colon].iter().enumerate() {
+            let val = hex_val(c as char).map_err(|e| e.at(b+i))?;
+            address = address.checked_mul(16).ok_or(EncodingError::InvalidAddress { offset: b })? + val as usize;
+        }
+        if address < bytes.len() || (address - bytes.len() > HEXDUMP_WIDTH && !after_run) {
+            return Err(EncodingError::InvalidAddress { offset: b });
+        }
+        bytes.try_reserve(address - bytes.len()).map_err(|_| EncodingError::InvalidAddress { offset: b })?;
+        bytes.resize(address, 0);
+        after_run = false;
+
+        let line_bytes = line.as_bytes();
+        let mut j = colon + 1;
+        while j < line_bytes.len() {
+            if line_bytes[j] == b' ' {
+                if line_bytes.get(j+1) == Some(&b' ') {
+                    break;
+                }
+                j += 1;
+                continue;
+            }
+
+            let hi = hex_val(line_bytes[j] as char).map_err(|e| e.at(b+j))?;
+            let lo = match line_bytes.get(j+1) {
+                Some(&c) => hex_val(c as char).map_err(|e| e.at(b+j+1))?,
+                None => return Err(EncodingError::InvalidLength { len: line_bytes.len() - colon - 1, multiple: 2 }),
+            };
+            bytes.push((hi << 4) | lo);
+            j += 2;
+        }
+    }
+
+    return Ok(bytes);
 }
 
-pub fn hex_to_base64(str: &str) -> String {
-    // since hex values are 4 bits wide and base64 values are 6 bits wide
-    // we can only encode 2 base64 sextets for every 3 hex quartets.
-    // thus the total number of hex quartets must be divisible by 3
-    if str.len() % 3 != 0 {
-        panic!("hex-encoded value cannot be converted to base64: {}", str);
+// an owned byte buffer that knows how to convert itself. it derefs to [u8], so
+// the functions above take it directly; Display prints it as hex, and Debug
+// prints a hexdump, which is easier to read in a failed assert_eq!
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct ByteBuf(pub Vec<u8>);
+
+impl ByteBuf {
+    pub fn new() -> ByteBuf {
+        return ByteBuf(Vec::new());
     }
 
-    let str_bytes = str.as_bytes();
+    pub fn from_hex(str: &str) -> Result<ByteBuf, EncodingError> {
+        return Ok(ByteBuf(hex_decode(str)?));
+    }
 
-    // encode 2 base64 bytes at a time
-    let mut b64_str: String = String::new();
-    for i in 0..(str_bytes.len()/3) {
-        let b: usize = i*3;
+    pub fn from_base64(str: &str) -> Result<ByteBuf, EncodingError> {
+        return Ok(ByteBuf(base64_decode(str)?));
+    }
 
-        let hex_val1 = hex_val(str_bytes[b] as char);
-        let hex_val2 = hex_val(str_bytes[b+1] as char);
-        let hex_val3 = hex_val(str_bytes[b+2] as char);
+    pub fn from_base64_with(str: &str, config: &Base64Config) -> Result<ByteBuf, EncodingError> {
+        return Ok(ByteBuf(base64_decode_with(str, config)?));
+    }
 
-        // first char
-        let b64_char1 = base64_char((hex_val1 << 2) | ((hex_val2 & 0b00001100) >> 2));
+    pub fn to_hex(&self) -> String {
+        return hex_encode(&self.0);
+    }
 
-        // second char
-        let b64_char2 = base64_char(((hex_val2 & 0b00000011) << 4) | hex_val3);
+    pub fn to_base64(&self) -> String {
+        return base64_encode(&self.0);
+    }
 
-        b64_str.push(b64_char1);
-        b64_str.push(b64_char2);
+    pub fn into_vec(self) -> Vec<u8> {
+        return self.0;
     }
+}
 
-    // encode to base64
-    return b64_str;
+impl Deref for ByteBuf {
+    type Target = [u8];
 
+    fn deref(&self) -> &[u8] {
+        return &self.0;
+    }
 }
 
-pub fn base64_to_ascii_str(str: &str) -> String {
-    if str.len() % 4 != 0 {
-        panic!("base64 string length must be divisible by 4 to convert to ascii");
+impl DerefMut for ByteBuf {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        return &mut self.0;
     }
+}
 
-    let str_bytes = str.as_bytes();
-    let mut ascii_string: String = String::new();
-    for i in 0..str_bytes.len()/4 {
-        let b: usize = i * 4;
-        let val1 = base64_val(str_bytes[b] as char);
-        let val2 = base64_val(str_bytes[b+1] as char);
-        let val3 = base64_val(str_bytes[b+2] as char);
-        let val4 = base64_val(str_bytes[b+3] as char);
+impl AsRef<[u8]> for ByteBuf {
+    fn as_ref(&self) -> &[u8] {
+        return &self.0;
+    }
+}
 
-        let byte1: u8 = ((val1 << 2) & 0b11111100) | ((val2 >> 4) & 0b00000011);
-        let byte2: u8 = ((val2 << 4) & 0b11110000) | ((val3 >> 2) & 0b00001111);
-        let byte3: u8 = ((val3 << 6) & 0b11000000) | (val4  & 0b00111111);
+impl From<Vec<u8>> for ByteBuf {
+    fn from(bytes: Vec<u8>) -> ByteBuf {
+        return ByteBuf(bytes);
+    }
+}
 
-        ascii_string.push(byte1 as char);
+impl From<&[u8]> for ByteBuf {
+    fn from(bytes: &[u8]) -> ByteBuf {
+        return ByteBuf(bytes.to_vec());
+    }
+}
 
-        if val4 == BASE64_PADDING_VAL {
-            // one '=' padding, only decode two bytes
-            if val3 != BASE64_PADDING_VAL {
-                let byte2: u8 = ((val2 << 4) & 0b11110000) | ((val3 >> 2) & 0b00001111);
-                ascii_string.push(byte2 as char);
-            }
+impl From<&str> for ByteBuf {
+    fn from(str: &str) -> ByteBuf {
+        return ByteBuf(str.as_bytes().to_vec());
+    }
+}
 
-        } else { // no '=' padding, decode all three possible bytes
-            ascii_string.push(byte2 as char);
-            ascii_string.push(byte3 as char);
+impl From<ByteBuf> for Vec<u8> {
+    fn from(buf: ByteBuf) -> Vec<u8> {
+        return buf.0;
+    }
+}
+
+// parses hex, so that "...".parse::<ByteBuf>() reads a challenge's input
+impl FromStr for ByteBuf {
+    type Err = EncodingError;
+
+    fn from_str(str: &str) -> Result<ByteBuf, EncodingError> {
+        return ByteBuf::from_hex(str);
+    }
+}
+
+impl fmt::Display for ByteBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.write_str(&hex_encode(&self.0));
+    }
+}
+
+impl fmt::Debug for ByteBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ByteBuf({} bytes)", self.0.len())?;
+        for line in hexdump(&self.0).lines() {
+            write!(f, "\n{}", line)?;
         }
+        return Ok(());
     }
+}
 
-    return ascii_string;
+// XOR of two buffers of the same length. like slice indexing, this panics
+// on a length mismatch; use xor_bytes to get an error instead
+impl BitXor<&ByteBuf> for &ByteBuf {
+    type Output = ByteBuf;
+
+    fn bitxor(self, rhs: &ByteBuf) -> ByteBuf {
+        return ByteBuf(xor_bytes(&self.0, &rhs.0).unwrap_or_else(|e| panic!("{}", e)));
+    }
+}
+
+impl BitXor<&ByteBuf> for ByteBuf {
+    type Output = ByteBuf;
+
+    fn bitxor(mut self, rhs: &ByteBuf) -> ByteBuf {
+        self ^= rhs;
+        return self;
+    }
+}
+
+// XOR every byte with a single-byte key
+impl BitXor<u8> for &ByteBuf {
+    type Output = ByteBuf;
+
+    fn bitxor(self, rhs: u8) -> ByteBuf {
+        return ByteBuf(self.0.iter().map(|b| b ^ rhs).collect());
+    }
+}
+
+impl BitXorAssign<&ByteBuf> for ByteBuf {
+    fn bitxor_assign(&mut self, rhs: &ByteBuf) {
+        xor_assign(&mut self.0, &rhs.0).unwrap_or_else(|e| panic!("{}", e));
+    }
 }
 
 #[cfg(test)]
@@ -211,8 +868,299 @@ mod test {
 
         let mut n: usize = 0;
         for _ in 0..500 {
-            let res = xor_bytes(&bytes1, &bytes2);
+            let res = xor_bytes(&bytes1, &bytes2).unwrap();
             n += res.len();
         }
+        assert_eq!(n, 500 * bytes1.len());
     }
-}
\ No newline at end of file
+
+    // same work as test_xor_bytes1, but reusing one output buffer
+    #[test]
+    fn test_xor_into_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes1 = vec![0u8; 1000000];
+        let mut bytes2 = vec![0u8; 1000000];
+        rng.fill(&mut bytes1[..]);
+        rng.fill(&mut bytes2[..]);
+
+        let mut out = vec![0u8; bytes1.len()];
+        for _ in 0..500 {
+            xor_into(&mut out, &bytes1, &bytes2).unwrap();
+        }
+        assert_eq!(out, xor_bytes(&bytes1, &bytes2).unwrap());
+
+        // XORing the same key in twice gives back the original
+        let orig = bytes1.clone();
+        xor_assign(&mut bytes1, &bytes2).unwrap();
+        xor_assign(&mut bytes1, &bytes2).unwrap();
+        assert_eq!(bytes1, orig);
+
+        assert_eq!(xor_assign(&mut out[..3], &bytes2[..4]), Err(EncodingError::LengthMismatch { left: 3, right: 4 }));
+        assert_eq!(xor_into(&mut out[..3], &bytes1[..4], &bytes2[..4]), Err(EncodingError::LengthMismatch { left: 3, right: 4 }));
+    }
+
+    #[test]
+    fn test_xor_with_key() {
+        assert_eq!(xor_with_key(b"", b"ICE").unwrap(), b"");
+        assert_eq!(xor_with_key(b"\x00\x01\x02\x03", b"\x10").unwrap(), b"\x10\x11\x12\x13");
+        assert_eq!(xor_with_key(b"abcde", b"\x01\x02").unwrap(), b"``bfd");
+        assert_eq!(xor_with_key(b"ab", b"\x01\x02\x03\x04").unwrap(), b"``");
+        assert_eq!(xor_with_key(b"abc", b""), Err(EncodingError::EmptyKey));
+
+        // a cycled key as a keystream is the same as xor_with_key
+        let mut bytes = b"abcde".to_vec();
+        xor_with_keystream(&mut bytes, [1u8, 2].iter().cloned().cycle());
+        assert_eq!(bytes, b"``bfd");
+
+        // a short keystream leaves the rest alone
+        let mut bytes = b"abcde".to_vec();
+        xor_with_keystream(&mut bytes, [1u8, 2].iter().cloned());
+        assert_eq!(bytes, b"``cde");
+    }
+
+    // compare with simd::test::test_hex_speed
+    #[test]
+    fn test_hex_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000000];
+        rng.fill(&mut bytes[..]);
+
+        for _ in 0..20 {
+            let encoded = hex_encode(&bytes);
+            assert_eq!(hex_decode(&encoded).unwrap().len(), bytes.len());
+        }
+    }
+
+    // compare with simd::test::test_base64_speed
+    #[test]
+    fn test_base64_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000000];
+        rng.fill(&mut bytes[..]);
+
+        for _ in 0..20 {
+            let encoded = base64_encode(&bytes);
+            assert_eq!(base64_decode(&encoded).unwrap().len(), bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_encoding_errors() {
+        assert_eq!(xor_bytes(b"ab", b"abc"), Err(EncodingError::LengthMismatch { left: 2, right: 3 }));
+
+        assert_eq!(hex_to_ascii_str("abc"), Err(EncodingError::InvalidLength { len: 3, multiple: 2 }));
+        assert_eq!(hex_to_ascii_str("ab\n"), Err(EncodingError::InvalidLength { len: 3, multiple: 2 }));
+        assert_eq!(hex_to_ascii_str("a\nbc"), Err(EncodingError::InvalidDigit { byte: b'\n', offset: 1 }));
+        assert_eq!(hex_to_base64("abcg"), Err(EncodingError::InvalidDigit { byte: b'g', offset: 3 }));
+        assert_eq!(hex_to_base64("abc"), Err(EncodingError::InvalidLength { len: 3, multiple: 2 }));
+
+        assert_eq!(base64_to_ascii_str("QUJD\n"), Err(EncodingError::InvalidLength { len: 5, multiple: 4 }));
+        assert_eq!(base64_to_ascii_str("QU*D"), Err(EncodingError::InvalidDigit { byte: b'*', offset: 2 }));
+        assert_eq!(base64_to_ascii_str("QU=D"), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(base64_to_ascii_str("Q===QUJD"), Err(EncodingError::InvalidPadding { offset: 1 }));
+        assert_eq!(base64_to_ascii_str("QQ==QUJD"), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(base64_to_ascii_str("QUI=QUJD"), Err(EncodingError::InvalidPadding { offset: 3 }));
+
+        assert_eq!(base64_to_ascii_str("QQ==").unwrap(), "A");
+        assert_eq!(base64_to_ascii_str("QUI=").unwrap(), "AB");
+        assert_eq!(base64_to_ascii_str("QUJD").unwrap(), "ABC");
+
+        assert_eq!(hex_decode("00ff80"), Ok(vec![0x00, 0xff, 0x80]));
+        assert_eq!(hex_char(16), Err(EncodingError::InvalidDigit { byte: 16, offset: 0 }));
+        assert_eq!(base64_char(64), Err(EncodingError::InvalidDigit { byte: 64, offset: 0 }));
+
+        // a char outside ASCII is rejected, not cut down to its low byte ('1' and 'A' here)
+        assert_eq!(hex_val('\u{131}'), Err(EncodingError::InvalidDigit { byte: 0xc4, offset: 0 }));
+        assert_eq!(hex_val('\u{e9}'), Err(EncodingError::InvalidDigit { byte: 0xe9, offset: 0 }));
+        assert_eq!(base64_val('\u{141}'), Err(EncodingError::InvalidDigit { byte: 0xc5, offset: 0 }));
+        assert_eq!(base64_val_with('\u{141}', Base64Alphabet::UrlSafe), Err(EncodingError::InvalidDigit { byte: 0xc5, offset: 0 }));
+    }
+
+    #[test]
+    fn test_byte_buf() {
+        let buf: ByteBuf = "49276d206b696c6c696e6720796f7572".parse().unwrap();
+        assert_eq!(&buf[..], b"I'm killing your");
+        assert_eq!(buf.len(), 16);
+        assert_eq!(buf.to_string(), "49276d206b696c6c696e6720796f7572");
+        assert_eq!(buf.to_base64(), "SSdtIGtpbGxpbmcgeW91cg==");
+        assert_eq!(ByteBuf::from_base64("SSdtIGtpbGxpbmcgeW91cg==").unwrap(), buf);
+        assert_eq!(ByteBuf::from_hex("4g"), Err(EncodingError::InvalidDigit { byte: b'g', offset: 1 }));
+        assert_eq!("4".parse::<ByteBuf>(), Err(EncodingError::InvalidLength { len: 1, multiple: 2 }));
+
+        assert_eq!(format!("{:?}", ByteBuf::from("I'm killing your brain\n")),
+                   "ByteBuf(23 bytes)\n\
+                    00000000: 4927 6d20 6b69 6c6c 696e 6720 796f 7572  I'm killing your\n\
+                    00000010: 2062 7261 696e 0a                         brain.");
+        assert_eq!(format!("{:?}", ByteBuf::new()), "ByteBuf(0 bytes)");
+
+        let key = ByteBuf::from(vec![0x20; 16]);
+        assert_eq!(&(&buf ^ &key)[..], b"i\x07M\x00KILLING\x00YOUR");
+        assert_eq!(&buf ^ 0x20, &buf ^ &key);
+        assert_eq!(buf.clone() ^ &key ^ &key, buf);
+    }
+
+    #[test]
+    fn test_hexdump() {
+        // as printed by xxd
+        let dump = "00000000: 4927 6d20 6b69 6c6c 696e 6720 796f 7572  I'm killing your\n\
+                    00000010: 2062 7261 696e 0a                         brain.\n";
+        assert_eq!(hexdump(b"I'm killing your brain\n"), dump);
+        assert_eq!(hexdump_decode(dump).unwrap(), b"I'm killing your brain\n");
+        assert_eq!(hexdump(b""), "");
+
+        let mut rng = rand::thread_rng();
+        for len in [1, 15, 16, 17, 100, 1000] {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+            assert_eq!(hexdump_decode(&hexdump(&bytes)).unwrap(), bytes);
+        }
+
+        // hand-edited dumps: no ASCII column, uppercase, CRLF, and a skipped address
+        assert_eq!(hexdump_decode("0: 4142 43\r\n00000010: FF\n").unwrap(),
+                   b"ABC\0\0\0\0\0\0\0\0\0\0\0\0\0\xff");
+
+        assert_eq!(hexdump_decode("00000000: 41\n*\n00001000: 42").unwrap().len(), 0x1001);
+        assert_eq!(hexdump_decode("ffffffffffff: 00"), Err(EncodingError::InvalidAddress { offset: 0 }));
+        assert_eq!(hexdump_decode("00000000: 41\n00000020: 42"), Err(EncodingError::InvalidAddress { offset: 13 }));
+        assert_eq!(hexdump_decode("*\nffffffffffffffff: 00"), Err(EncodingError::InvalidAddress { offset: 2 }));
+        assert_eq!(hexdump_decode("00000000: 41 42\n4142"), Err(EncodingError::InvalidAddress { offset: 16 }));
+        assert_eq!(hexdump_decode("00000010: 41\n00000000: 42"), Err(EncodingError::InvalidAddress { offset: 13 }));
+        assert_eq!(hexdump_decode("0000000g: 41"), Err(EncodingError::InvalidDigit { byte: b'g', offset: 7 }));
+        assert_eq!(hexdump_decode("0000000\u{131}: 41"), Err(EncodingError::InvalidDigit { byte: 0xc4, offset: 7 }));
+        assert_eq!(hexdump_decode("00000000: 41\n\u{131}0000000: 42"), Err(EncodingError::InvalidDigit { byte: 0xc4, offset: 13 }));
+        assert_eq!(hexdump_decode("00000000: 4x"), Err(EncodingError::InvalidDigit { byte: b'x', offset: 11 }));
+        assert_eq!(hexdump_decode("00000000: 414 2"), Err(EncodingError::InvalidDigit { byte: b' ', offset: 13 }));
+        assert_eq!(hexdump_decode("00000000: 414"), Err(EncodingError::InvalidLength { len: 4, multiple: 2 }));
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        let hex = hex_encode(&bytes);
+        assert_eq!(hex.len(), 512);
+        assert_eq!(hex_decode(&hex).unwrap(), bytes);
+
+        for len in 0..bytes.len() {
+            let b64 = base64_encode(&bytes[..len]);
+            assert_eq!(base64_decode(&b64).unwrap(), &bytes[..len]);
+        }
+
+        // the string helpers keep one char per byte, so they round-trip too
+        let latin1 = hex_to_ascii_str("c3a9ff").unwrap();
+        assert_eq!(latin1, "\u{c3}\u{a9}\u{ff}");
+        assert_eq!(latin1.chars().map(|c| c as u8).collect::<Vec<u8>>(), hex_decode("c3a9ff").unwrap());
+    }
+
+    // test vectors from RFC 4648, section 10
+    #[test]
+    fn test_base64_rfc4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(bytes_to_base64(plain.as_bytes()), *encoded);
+            assert_eq!(base64_to_ascii_str(encoded).unwrap(), *plain);
+            assert_eq!(hex_to_base64(&ascii_to_hex_str(plain)).unwrap(), *encoded);
+        }
+    }
+
+    #[test]
+    fn test_hex_case_and_separators() {
+        let bytes = vec![0xde, 0xad, 0xbe, 0xef];
+
+        assert_eq!(hex_decode("DEADbeef").unwrap(), bytes);
+        assert_eq!(hex_encode_with(&bytes, &HexConfig::UPPER), "DEADBEEF");
+        assert_eq!(hex_encode_with(&bytes, &HexConfig::LOWER), hex_encode(&bytes));
+
+        assert_eq!(hex_decode_with("0xDE 0xAD 0xBE 0xEF", &HexConfig::LENIENT).unwrap(), bytes);
+        assert_eq!(hex_decode_with("de:ad:be:ef\n", &HexConfig::LENIENT).unwrap(), bytes);
+        assert_eq!(hex_decode_with("0XdeadBEEF", &HexConfig::LENIENT).unwrap(), bytes);
+        assert_eq!(hex_decode_with("", &HexConfig::LENIENT).unwrap(), vec![]);
+
+        // strict decoding and separators inside a byte are still rejected
+        assert_eq!(hex_decode_with("de:ad", &HexConfig::UPPER), Err(EncodingError::InvalidLength { len: 5, multiple: 2 }));
+        assert_eq!(hex_decode_with("de a", &HexConfig::LENIENT), Err(EncodingError::InvalidLength { len: 3, multiple: 2 }));
+        assert_eq!(hex_decode_with("0xa", &HexConfig::LENIENT), Err(EncodingError::InvalidLength { len: 1, multiple: 2 }));
+        assert_eq!(hex_decode_with("0xde 0x0a 0", &HexConfig::LENIENT), Err(EncodingError::InvalidLength { len: 5, multiple: 2 }));
+        assert_eq!(hex_decode_with("d e", &HexConfig::LENIENT), Err(EncodingError::InvalidDigit { byte: b' ', offset: 1 }));
+    }
+
+    #[test]
+    fn test_base64_variants() {
+        let bytes: Vec<u8> = vec![0xfb, 0xff, 0xbf, 0x61];
+
+        assert_eq!(base64_encode_with(&bytes, &Base64Config::STANDARD), "+/+/YQ==");
+        assert_eq!(base64_encode_with(&bytes, &Base64Config::STANDARD_NO_PAD), "+/+/YQ");
+        assert_eq!(base64_encode_with(&bytes, &Base64Config::URL_SAFE), "-_-_YQ==");
+        assert_eq!(base64_encode_with(&bytes, &Base64Config::URL_SAFE_NO_PAD), "-_-_YQ");
+
+        for config in [Base64Config::STANDARD, Base64Config::STANDARD_NO_PAD,
+                       Base64Config::URL_SAFE, Base64Config::URL_SAFE_NO_PAD,
+                       Base64Config::MIME].iter() {
+            for len in 0..bytes.len() {
+                let encoded = base64_encode_with(&bytes[..len], config);
+                assert_eq!(base64_decode_with(&encoded, config).unwrap(), &bytes[..len]);
+            }
+        }
+
+        // alphabets and padding modes do not mix
+        assert_eq!(base64_decode_with("-_-_YQ==", &Base64Config::STANDARD), Err(EncodingError::InvalidDigit { byte: b'-', offset: 0 }));
+        assert_eq!(base64_decode_with("+/+/YQ==", &Base64Config::URL_SAFE), Err(EncodingError::InvalidDigit { byte: b'+', offset: 0 }));
+        assert_eq!(base64_decode_with("+/+/YQ==", &Base64Config::STANDARD_NO_PAD), Err(EncodingError::InvalidPadding { offset: 6 }));
+        assert_eq!(base64_decode_with("+/+/Y", &Base64Config::STANDARD_NO_PAD), Err(EncodingError::InvalidLength { len: 5, multiple: 4 }));
+
+        // MIME wraps at 76 columns and skips whitespace on the way back in
+        let long: Vec<u8> = (0..=255).collect();
+        let mime = base64_encode_with(&long, &Base64Config::MIME);
+        assert!(mime.split("\r\n").all(|line| line.len() <= 76));
+        assert_eq!(mime.split("\r\n").next().unwrap().len(), 76);
+        assert_eq!(base64_decode_with(&mime, &Base64Config::MIME).unwrap(), long);
+        assert_eq!(base64_decode_with(" QU\nJD\t", &Base64Config::MIME).unwrap(), b"ABC");
+        assert_eq!(base64_decode_with("QU\nJ*", &Base64Config::MIME), Err(EncodingError::InvalidDigit { byte: b'*', offset: 4 }));
+        assert_eq!(base64_decode("QU\nJD"), Err(EncodingError::InvalidLength { len: 5, multiple: 4 }));
+    }
+
+    #[test]
+    fn test_base64_modes() {
+        // "QR==" and "QUJ=" carry bits past the end of their data
+        assert_eq!(base64_decode("QR==").unwrap(), b"A");
+        assert_eq!(base64_decode_with("QR==", &Base64Config::STRICT), Err(EncodingError::NonCanonical { byte: b'R', offset: 1 }));
+        assert_eq!(base64_decode_with("QUJ=", &Base64Config::STRICT), Err(EncodingError::NonCanonical { byte: b'J', offset: 2 }));
+        assert_eq!(base64_decode_with("QUI=", &Base64Config::STRICT).unwrap(), b"AB");
+        assert_eq!(base64_decode_with("QUJD", &Base64Config::STRICT).unwrap(), b"ABC");
+
+        let strict_jwt = Base64Config { mode: Base64Mode::Strict, ..Base64Config::URL_SAFE_NO_PAD };
+        assert_eq!(base64_decode_with("QR", &strict_jwt), Err(EncodingError::NonCanonical { byte: b'R', offset: 1 }));
+        assert_eq!(base64_decode_with("QQ", &strict_jwt).unwrap(), b"A");
+
+        // strict still rejects misplaced padding and wrong lengths
+        assert_eq!(base64_decode_with("QQ==QUJD", &Base64Config::STRICT), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(base64_decode_with("QUI", &Base64Config::STRICT), Err(EncodingError::InvalidLength { len: 3, multiple: 4 }));
+
+        // lenient takes whitespace and missing or present padding
+        assert_eq!(base64_decode_with(" QUJD\r\nQUI ", &Base64Config::LENIENT).unwrap(), b"ABCAB");
+        assert_eq!(base64_decode_with("QUJDQUI=\n", &Base64Config::LENIENT).unwrap(), b"ABCAB");
+        assert_eq!(base64_decode_with("QQ", &Base64Config::LENIENT).unwrap(), b"A");
+        assert_eq!(base64_decode_with("QR", &Base64Config::LENIENT).unwrap(), b"A");
+        assert_eq!(base64_decode_with("QQ=", &Base64Config::LENIENT).unwrap(), b"A");
+
+        // but not garbage or padding in the middle
+        assert_eq!(base64_decode_with("QUJDQ", &Base64Config::LENIENT), Err(EncodingError::InvalidLength { len: 5, multiple: 4 }));
+        assert_eq!(base64_decode_with("QQ==QUJD", &Base64Config::LENIENT), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(base64_decode_with("QU.D", &Base64Config::LENIENT), Err(EncodingError::InvalidDigit { byte: b'.', offset: 2 }));
+    }
+}
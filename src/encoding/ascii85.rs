@@ -0,0 +1,195 @@
+use std::convert::TryFrom;
+use crate::encoding::EncodingError;
+
+// ZeroMQ's Z85 alphabet (RFC 32), which avoids quotes and backslashes
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+// split a 4-byte group into base-85 digits, most significant first
+fn base85_digits(group: u32) -> [u8; 5] {
+    let mut digits = [0u8; 5];
+    let mut val = group;
+    for digit in digits.iter_mut().rev() {
+        *digit = (val % 85) as u8;
+        val /= 85;
+    }
+    return digits;
+}
+
+// join base-85 digits back into a 4-byte group. start is the offset of the
+// group's first character, for the error when the digits exceed 32 bits
+fn base85_group(digits: &[u8; 5], start: usize) -> Result<u32, EncodingError> {
+    let mut val: u64 = 0;
+    for &digit in digits {
+        val = val * 85 + digit as u64;
+    }
+    return u32::try_from(val).map_err(|_| EncodingError::ValueOverflow { offset: start });
+}
+
+// encode raw bytes as Ascii85 (btoa / Adobe style, without the "<~" "~>" delimiters).
+// an all-zero group becomes 'z', and a final group of n bytes becomes n+1 characters
+pub fn ascii85_encode(bytes: &[u8]) -> String {
+    let mut a85_str: String = String::with_capacity(bytes.len().div_ceil(4)*5);
+    for chunk in bytes.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let group = u32::from_be_bytes(group);
+
+        if group == 0 && chunk.len() == 4 {
+            a85_str.push('z');
+            continue;
+        }
+
+        for &digit in &base85_digits(group)[..chunk.len()+1] {
+            a85_str.push((digit + 33) as char);
+        }
+    }
+
+    return a85_str;
+}
+
+// decode Ascii85 into raw bytes. whitespace is skipped, and a surrounding "<~" "~>" pair is removed
+pub fn ascii85_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+    let start = if str_bytes.starts_with(b"<~") { 2 } else { 0 };
+    let end = if str_bytes[start..].ends_with(b"~>") { str_bytes.len() - 2 } else { str_bytes.len() };
+
+    let mut bytes: Vec<u8> = Vec::with_capacity((end - start)/5*4 + 3);
+    let mut digits = [0u8; 5];
+    let mut n_digits: usize = 0;
+    let mut group_start: usize = start;
+    let mut n_chars: usize = 0;
+    for (i, &c) in str_bytes.iter().enumerate().take(end).skip(start) {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        n_chars += 1;
+
+        if n_digits == 0 {
+            group_start = i;
+        }
+
+        match c {
+            // 'z' stands for a whole group of zeros, so it cannot appear inside a group
+            b'z' if n_digits == 0 => {
+                bytes.extend_from_slice(&[0; 4]);
+                continue;
+            },
+
+            33..=117 => { // '!'..'u'
+                digits[n_digits] = c - 33;
+                n_digits += 1;
+            },
+
+            _ => return Err(EncodingError::InvalidDigit { byte: c, offset: i }),
+        }
+
+        if n_digits == 5 {
+            bytes.extend_from_slice(&base85_group(&digits, group_start)?.to_be_bytes());
+            n_digits = 0;
+        }
+    }
+
+    // a final group of n+1 characters carries n bytes, padded out with 'u' (the largest digit)
+    if n_digits == 1 {
+        return Err(EncodingError::InvalidLength { len: n_chars, multiple: 5 });
+    }
+    if n_digits > 1 {
+        for digit in digits.iter_mut().skip(n_digits) {
+            *digit = 84;
+        }
+        let group = base85_group(&digits, group_start)?.to_be_bytes();
+        bytes.extend_from_slice(&group[..n_digits-1]);
+    }
+
+    return Ok(bytes);
+}
+
+// encode raw bytes as Z85. the spec has no partial groups, so the length must be a multiple of 4
+pub fn z85_encode(bytes: &[u8]) -> Result<String, EncodingError> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(EncodingError::InvalidLength { len: bytes.len(), multiple: 4 });
+    }
+
+    let mut z85_str: String = String::with_capacity(bytes.len()/4*5);
+    for chunk in bytes.chunks_exact(4) {
+        let group = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        for &digit in &base85_digits(group) {
+            z85_str.push(Z85_ALPHABET[digit as usize] as char);
+        }
+    }
+
+    return Ok(z85_str);
+}
+
+// decode Z85 into raw bytes
+pub fn z85_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    if !str.len().is_multiple_of(5) {
+        return Err(EncodingError::InvalidLength { len: str.len(), multiple: 5 });
+    }
+
+    let str_bytes = str.as_bytes();
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/5*4);
+    for (i, chunk) in str_bytes.chunks_exact(5).enumerate() {
+        let b = i * 5;
+        let mut digits = [0u8; 5];
+        for (j, &c) in chunk.iter().enumerate() {
+            digits[j] = match Z85_ALPHABET.iter().position(|&a| a == c) {
+                Some(val) => val as u8,
+                None => return Err(EncodingError::InvalidDigit { byte: c, offset: b+j }),
+            };
+        }
+        bytes.extend_from_slice(&base85_group(&digits, b)?.to_be_bytes());
+    }
+
+    return Ok(bytes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ascii85_vectors() {
+        let vectors: [(&[u8], &str); 8] = [
+            (b"", ""),
+            (b"Man ", "9jqo^"),
+            (b"Man is", "9jqo^Bla"),
+            (b"\0\0\0\0", "z"),
+            (b"\0\0\0\0\x01", "z!<"),
+            (b"\0\0\0", "!!!!"),
+            (b"\xff\xff\xff\xff", "s8W-!"),
+            (b"hello world", "BOu!rD]j7BEbo7"),
+        ];
+
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(ascii85_encode(plain), *encoded);
+            assert_eq!(ascii85_decode(encoded).unwrap(), *plain);
+        }
+
+        // delimiters and line breaks as written by btoa and PostScript
+        assert_eq!(ascii85_decode("<~BOu!rD]j\n7BEbo7~>").unwrap(), b"hello world");
+
+        assert_eq!(ascii85_decode("9jqo^B"), Err(EncodingError::InvalidLength { len: 6, multiple: 5 }));
+        assert_eq!(ascii85_decode("9jzo^"), Err(EncodingError::InvalidDigit { byte: b'z', offset: 2 }));
+        assert_eq!(ascii85_decode("9jqo~"), Err(EncodingError::InvalidDigit { byte: b'~', offset: 4 }));
+        assert_eq!(ascii85_decode("9jqo^s8W-\""), Err(EncodingError::ValueOverflow { offset: 5 }));
+    }
+
+    // test vector from the Z85 spec (ZeroMQ RFC 32)
+    #[test]
+    fn test_z85_vectors() {
+        let bytes = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(z85_encode(&bytes).unwrap(), "HelloWorld");
+        assert_eq!(z85_decode("HelloWorld").unwrap(), bytes);
+
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(z85_decode(&z85_encode(&all).unwrap()).unwrap(), all);
+
+        assert_eq!(z85_encode(b"abc"), Err(EncodingError::InvalidLength { len: 3, multiple: 4 }));
+        assert_eq!(z85_decode("Hello"), Ok(vec![0x86, 0x4f, 0xd2, 0x6f]));
+        assert_eq!(z85_decode("Hell"), Err(EncodingError::InvalidLength { len: 4, multiple: 5 }));
+        assert_eq!(z85_decode("Hel\"o"), Err(EncodingError::InvalidDigit { byte: b'"', offset: 3 }));
+        assert_eq!(z85_decode("#####"), Err(EncodingError::ValueOverflow { offset: 0 }));
+    }
+}
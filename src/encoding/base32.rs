@@ -0,0 +1,195 @@
+use crate::encoding::{invalid_digit, EncodingError};
+
+const BASE32_PADDING_VAL: u8 = 255;
+
+// number of base32 characters that carry n bytes of a final 5-byte group
+const BASE32_CHARS_FOR_BYTES: [usize; 6] = [0, 2, 4, 5, 7, 8];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base32Alphabet {
+    Standard, // A-Z 2-7 (RFC 4648 section 6, used for TOTP secrets)
+    Hex, // 0-9 A-V (RFC 4648 section 7, sorts in the same order as the data)
+}
+
+// options for base32_encode_with and base32_decode_with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base32Config {
+    pub alphabet: Base32Alphabet,
+
+    // emit '=' padding when encoding and require it when decoding.
+    // without padding, '=' in the input is rejected
+    pub pad: bool,
+}
+
+impl Base32Config {
+    pub const STANDARD: Base32Config = Base32Config { alphabet: Base32Alphabet::Standard, pad: true };
+    pub const STANDARD_NO_PAD: Base32Config = Base32Config { alphabet: Base32Alphabet::Standard, pad: false };
+    pub const HEX: Base32Config = Base32Config { alphabet: Base32Alphabet::Hex, pad: true };
+}
+
+pub fn base32_char(b: u8, alphabet: Base32Alphabet) -> Result<char, EncodingError> {
+    let val = match (b, alphabet) {
+        (0..=25, Base32Alphabet::Standard) => b + 65, // A-Z
+        (26..=31, Base32Alphabet::Standard) => b - 26 + 50, // 2-7
+        (0..=9, Base32Alphabet::Hex) => b + 48, // 0-9
+        (10..=31, Base32Alphabet::Hex) => b - 10 + 65, // A-V
+        _ => return Err(EncodingError::InvalidDigit { byte: b, offset: 0 }),
+    };
+    return Ok(val as char);
+}
+
+// decoding accepts lowercase letters too, since TOTP secrets are often written that way
+pub fn base32_val(c: char, alphabet: Base32Alphabet) -> Result<u8, EncodingError> {
+    if !c.is_ascii() {
+        return Err(invalid_digit(c));
+    }
+
+    let c_ascii = c as u8;
+    let val = match (c_ascii.to_ascii_uppercase(), alphabet) {
+        (65..=90, Base32Alphabet::Standard) => c_ascii.to_ascii_uppercase() - 65, // A-Z
+        (50..=55, Base32Alphabet::Standard) => c_ascii - 50 + 26, // 2-7
+        (48..=57, Base32Alphabet::Hex) => c_ascii - 48, // 0-9
+        (65..=86, Base32Alphabet::Hex) => c_ascii.to_ascii_uppercase() - 65 + 10, // A-V
+        (61, _) => BASE32_PADDING_VAL, // = (padding)
+        _ => return Err(EncodingError::InvalidDigit { byte: c_ascii, offset: 0 }),
+    };
+    return Ok(val);
+}
+
+// encode raw bytes as padded RFC 4648 base32
+pub fn base32_encode(bytes: &[u8]) -> String {
+    return base32_encode_with(bytes, &Base32Config::STANDARD);
+}
+
+// encode raw bytes as base32 in the variant described by config
+pub fn base32_encode_with(bytes: &[u8], config: &Base32Config) -> String {
+    let mut b32_str: String = String::with_capacity(bytes.len().div_ceil(5)*8);
+    for chunk in bytes.chunks(5) {
+        // the group as a 40-bit number, zero-filled on the right
+        let mut group: u64 = 0;
+        for (i, &b) in chunk.iter().enumerate() {
+            group |= (b as u64) << (32 - 8*i);
+        }
+
+        let n_chars = BASE32_CHARS_FOR_BYTES[chunk.len()];
+        for i in 0..n_chars {
+            let quintet = ((group >> (35 - 5*i)) & 0b11111) as u8;
+            b32_str.push(base32_char(quintet, config.alphabet).unwrap());
+        }
+
+        if config.pad {
+            for _ in n_chars..8 {
+                b32_str.push('=');
+            }
+        }
+    }
+
+    return b32_str;
+}
+
+// decode padded RFC 4648 base32 into raw bytes
+pub fn base32_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    return base32_decode_with(str, &Base32Config::STANDARD);
+}
+
+// decode base32 in the variant described by config
+pub fn base32_decode_with(str: &str, config: &Base32Config) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+
+    // unpadded input may end in a short quantum, as long as it is one the encoder could produce
+    let len_ok =
+        if config.pad {
+            str_bytes.len().is_multiple_of(8)
+        } else {
+            BASE32_CHARS_FOR_BYTES.contains(&(str_bytes.len() % 8))
+        };
+    if !len_ok {
+        return Err(EncodingError::InvalidLength { len: str_bytes.len(), multiple: 8 });
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/8*5 + 4);
+    for (i, quantum) in str_bytes.chunks(8).enumerate() {
+        let b = i * 8;
+        let last_quantum = b + 8 >= str_bytes.len();
+
+        let mut group: u64 = 0;
+        let mut n_chars: usize = quantum.len();
+        for (j, &c) in quantum.iter().enumerate() {
+            let val = base32_val(c as char, config.alphabet).map_err(|e| e.at(b+j))?;
+
+            if val == BASE32_PADDING_VAL {
+                if !config.pad || !last_quantum {
+                    return Err(EncodingError::InvalidPadding { offset: b+j });
+                }
+                n_chars = n_chars.min(j);
+
+            } else if n_chars < j {
+                // data after padding started: the padding was misplaced
+                return Err(EncodingError::InvalidPadding { offset: b+n_chars });
+
+            } else {
+                group |= (val as u64) << (35 - 5*j);
+            }
+        }
+
+        // padding has to leave a whole number of bytes behind
+        let n_bytes = match BASE32_CHARS_FOR_BYTES.iter().position(|&n| n == n_chars) {
+            Some(n_bytes) => n_bytes,
+            None => return Err(EncodingError::InvalidPadding { offset: b+n_chars }),
+        };
+
+        for k in 0..n_bytes {
+            bytes.push((group >> (32 - 8*k)) as u8);
+        }
+    }
+
+    return Ok(bytes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // test vectors from RFC 4648, section 10
+    #[test]
+    fn test_base32_rfc4648() {
+        let vectors = [
+            ("", "", ""),
+            ("f", "MY======", "CO======"),
+            ("fo", "MZXQ====", "CPNG===="),
+            ("foo", "MZXW6===", "CPNMU==="),
+            ("foob", "MZXW6YQ=", "CPNMUOG="),
+            ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+            ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+        ];
+
+        for (plain, standard, hex) in vectors.iter() {
+            assert_eq!(base32_encode(plain.as_bytes()), *standard);
+            assert_eq!(base32_decode(standard).unwrap(), plain.as_bytes());
+            assert_eq!(base32_encode_with(plain.as_bytes(), &Base32Config::HEX), *hex);
+            assert_eq!(base32_decode_with(hex, &Base32Config::HEX).unwrap(), plain.as_bytes());
+
+            let unpadded = standard.trim_end_matches('=');
+            assert_eq!(base32_encode_with(plain.as_bytes(), &Base32Config::STANDARD_NO_PAD), unpadded);
+            assert_eq!(base32_decode_with(unpadded, &Base32Config::STANDARD_NO_PAD).unwrap(), plain.as_bytes());
+        }
+
+        // TOTP secrets are often lowercase
+        assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn test_base32_errors() {
+        assert_eq!(base32_decode("MZXW6YQ"), Err(EncodingError::InvalidLength { len: 7, multiple: 8 }));
+        assert_eq!(base32_decode_with("MZX", &Base32Config::STANDARD_NO_PAD), Err(EncodingError::InvalidLength { len: 3, multiple: 8 }));
+        assert_eq!(base32_decode("MZXW1YQ="), Err(EncodingError::InvalidDigit { byte: b'1', offset: 4 }));
+        assert_eq!(base32_decode_with("CPNMUOJW", &Base32Config::HEX), Err(EncodingError::InvalidDigit { byte: b'W', offset: 7 }));
+        assert_eq!(base32_val('\u{132}', Base32Alphabet::Standard), Err(EncodingError::InvalidDigit { byte: 0xc4, offset: 0 }));
+
+        // padding must be at the end, and must leave a whole number of bytes
+        assert_eq!(base32_decode("MY======MZXW6YTB"), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(base32_decode("MZX====="), Err(EncodingError::InvalidPadding { offset: 3 }));
+        assert_eq!(base32_decode("MZ=W6YQ="), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(base32_decode_with("MZXW6YQ=", &Base32Config::STANDARD_NO_PAD), Err(EncodingError::InvalidPadding { offset: 7 }));
+    }
+}
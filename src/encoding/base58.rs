@@ -0,0 +1,229 @@
+use crate::encoding::{invalid_digit, EncodingError};
+
+// the Bitcoin alphabet: no 0, O, I or l, so that addresses can be read aloud
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Base58Check appends the first 4 bytes of a double SHA-256 of the payload
+const BASE58_CHECKSUM_LEN: usize = 4;
+
+pub fn base58_char(b: u8) -> Result<char, EncodingError> {
+    return match BASE58_ALPHABET.get(b as usize) {
+        Some(&c) => Ok(c as char),
+        None => Err(EncodingError::InvalidDigit { byte: b, offset: 0 }),
+    };
+}
+
+pub fn base58_val(c: char) -> Result<u8, EncodingError> {
+    if !c.is_ascii() {
+        return Err(invalid_digit(c));
+    }
+
+    let c_ascii = c as u8;
+    return match BASE58_ALPHABET.iter().position(|&a| a == c_ascii) {
+        Some(val) => Ok(val as u8),
+        None => Err(EncodingError::InvalidDigit { byte: c_ascii, offset: 0 }),
+    };
+}
+
+// encode raw bytes as Base58. the input is read as one big-endian number,
+// so this is quadratic in the input length; it is meant for keys and addresses
+pub fn base58_encode(bytes: &[u8]) -> String {
+    // each leading zero byte is written as a leading '1'
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+
+    // base-58 digits of the number, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &b in &bytes[zeros..] {
+        let mut carry = b as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut b58_str: String = String::with_capacity(zeros + digits.len());
+    for _ in 0..zeros {
+        b58_str.push('1');
+    }
+    for &digit in digits.iter().rev() {
+        b58_str.push(base58_char(digit).unwrap());
+    }
+
+    return b58_str;
+}
+
+// decode Base58 into raw bytes
+pub fn base58_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+    let zeros = str_bytes.iter().take_while(|&&c| c == b'1').count();
+
+    // bytes of the number, least significant first
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len() * 733 / 1000 + 1);
+    for (i, &c) in str_bytes.iter().enumerate().skip(zeros) {
+        let mut carry = base58_val(c as char).map_err(|e| e.at(i))? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+    return Ok(bytes);
+}
+
+// encode a payload (usually a version byte followed by a hash) as Base58Check
+pub fn base58check_encode(payload: &[u8]) -> String {
+    let mut data = payload.to_vec();
+    data.extend_from_slice(&sha256(&sha256(payload))[..BASE58_CHECKSUM_LEN]);
+    return base58_encode(&data);
+}
+
+// decode Base58Check and return the payload with its checksum verified and removed
+pub fn base58check_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    let mut data = base58_decode(str)?;
+    if data.len() < BASE58_CHECKSUM_LEN {
+        return Err(EncodingError::BadChecksum);
+    }
+
+    let checksum = data.split_off(data.len() - BASE58_CHECKSUM_LEN);
+    if checksum[..] != sha256(&sha256(&data))[..BASE58_CHECKSUM_LEN] {
+        return Err(EncodingError::BadChecksum);
+    }
+
+    return Ok(data);
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// FIPS 180-4 SHA-256, only needed here for the Base58Check checksum
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    // pad with a 1 bit, zeros, and the message length in bits
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in msg.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i-15].rotate_right(7) ^ w[i-15].rotate_right(18) ^ (w[i-15] >> 3);
+            let s1 = w[i-2].rotate_right(17) ^ w[i-2].rotate_right(19) ^ (w[i-2] >> 10);
+            w[i] = w[i-16].wrapping_add(s0).wrapping_add(w[i-7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (hi, v) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *hi = hi.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (i, word) in h.iter().enumerate() {
+        digest[i*4 .. i*4+4].copy_from_slice(&word.to_be_bytes());
+    }
+    return digest;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoding::{hex_decode, hex_encode};
+
+    // test vectors from Bitcoin Core's base58_encode_decode.json
+    #[test]
+    fn test_base58_vectors() {
+        let vectors = [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            ("73696d706c792061206c6f6e6720737472696e67", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+            ("00eb15231dfceb60925886b67d065299925915aeb172c06647", "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L"),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ];
+
+        for (hex, encoded) in vectors.iter() {
+            let bytes = hex_decode(hex).unwrap();
+            assert_eq!(base58_encode(&bytes), *encoded);
+            assert_eq!(base58_decode(encoded).unwrap(), bytes);
+        }
+
+        assert_eq!(base58_decode("3EFU0m"), Err(EncodingError::InvalidDigit { byte: b'0', offset: 4 }));
+        assert_eq!(base58_decode("1Il"), Err(EncodingError::InvalidDigit { byte: b'I', offset: 1 }));
+        assert_eq!(base58_val('\u{131}'), Err(EncodingError::InvalidDigit { byte: 0xc4, offset: 0 }));
+    }
+
+    #[test]
+    fn test_base58check() {
+        // the P2PKH address from the Bitcoin wiki's "Technical background of version 1 addresses"
+        let payload = hex_decode("00f54a5851e9372b87810a8e60cdd2e7cfd80b6e31").unwrap();
+        let address = "1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAs";
+        assert_eq!(base58check_encode(&payload), address);
+        assert_eq!(base58check_decode(address).unwrap(), payload);
+
+        assert_eq!(base58check_decode("1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAt"), Err(EncodingError::BadChecksum));
+        assert_eq!(base58check_decode("2g"), Err(EncodingError::BadChecksum));
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(hex_encode(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex_encode(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hex_encode(&sha256(&[b'a'; 1000])),
+                   "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3");
+    }
+}
@@ -0,0 +1,185 @@
+use std::str;
+use bytes::{Buf, BufMut, Bytes};
+use crate::encoding::codec::codec;
+use crate::encoding::{EncodingError, HexConfig, Base64Config};
+
+// the routines in encoding, over the bytes crate's buffers. inputs are any
+// impl Buf (Bytes, BytesMut, &[u8], chains of those) and are consumed;
+// outputs are either appended to an impl BufMut or returned as Bytes.
+// everything goes through codec(), so the SIMD backends are used when available
+
+// run f over the unread part of src as one slice, then consume it. Bytes, BytesMut
+// and &[u8] are already contiguous; only chained buffers get copied
+fn with_contiguous<T>(mut src: impl Buf, f: impl FnOnce(&[u8]) -> T) -> T {
+    let len = src.remaining();
+    if src.bytes().len() == len {
+        let result = f(src.bytes());
+        src.advance(len);
+        return result;
+
+    } else {
+        let copy = src.to_bytes();
+        return f(&copy);
+    }
+}
+
+// the text decoders take &str. nothing outside ASCII is valid hex or base64,
+// so input that is not UTF-8 fails at the first byte that makes it so
+fn as_str(bytes: &[u8]) -> Result<&str, EncodingError> {
+    return str::from_utf8(bytes).map_err(|e| {
+        let offset = e.valid_up_to();
+        EncodingError::InvalidDigit { byte: bytes[offset], offset }
+    });
+}
+
+pub fn hex_encode_into(src: impl Buf, dst: &mut impl BufMut) {
+    hex_encode_into_with(src, dst, &HexConfig::LOWER);
+}
+
+pub fn hex_encode_into_with(src: impl Buf, dst: &mut impl BufMut, config: &HexConfig) {
+    let hex_str = with_contiguous(src, |bytes| codec().hex_encode_with(bytes, config));
+    dst.put_slice(hex_str.as_bytes());
+}
+
+pub fn hex_decode_into(src: impl Buf, dst: &mut impl BufMut) -> Result<(), EncodingError> {
+    return hex_decode_into_with(src, dst, &HexConfig::LOWER);
+}
+
+// nothing is written to dst if the input is invalid
+pub fn hex_decode_into_with(src: impl Buf, dst: &mut impl BufMut, config: &HexConfig) -> Result<(), EncodingError> {
+    let bytes = with_contiguous(src, |hex| codec().hex_decode_with(as_str(hex)?, config))?;
+    dst.put_slice(&bytes);
+    return Ok(());
+}
+
+pub fn base64_encode_into(src: impl Buf, dst: &mut impl BufMut) {
+    base64_encode_into_with(src, dst, &Base64Config::STANDARD);
+}
+
+pub fn base64_encode_into_with(src: impl Buf, dst: &mut impl BufMut, config: &Base64Config) {
+    let b64_str = with_contiguous(src, |bytes| codec().base64_encode_with(bytes, config));
+    dst.put_slice(b64_str.as_bytes());
+}
+
+pub fn base64_decode_into(src: impl Buf, dst: &mut impl BufMut) -> Result<(), EncodingError> {
+    return base64_decode_into_with(src, dst, &Base64Config::STANDARD);
+}
+
+// nothing is written to dst if the input is invalid
+pub fn base64_decode_into_with(src: impl Buf, dst: &mut impl BufMut, config: &Base64Config) -> Result<(), EncodingError> {
+    let bytes = with_contiguous(src, |b64| codec().base64_decode_with(as_str(b64)?, config))?;
+    dst.put_slice(&bytes);
+    return Ok(());
+}
+
+// append buf1 XOR buf2 to dst. both inputs are consumed, even on a length mismatch
+pub fn xor_into(buf1: impl Buf, buf2: impl Buf, dst: &mut impl BufMut) -> Result<(), EncodingError> {
+    let bytes = with_contiguous(buf1, |bytes1| {
+        with_contiguous(buf2, |bytes2| codec().xor_bytes(bytes1, bytes2))
+    })?;
+    dst.put_slice(&bytes);
+    return Ok(());
+}
+
+// the same routines returning Bytes. the Vec or String that the codec
+// produces becomes the Bytes without another copy
+
+pub fn hex_encode(src: impl Buf) -> Bytes {
+    return hex_encode_with(src, &HexConfig::LOWER);
+}
+
+pub fn hex_encode_with(src: impl Buf, config: &HexConfig) -> Bytes {
+    return Bytes::from(with_contiguous(src, |bytes| codec().hex_encode_with(bytes, config)));
+}
+
+pub fn hex_decode(src: impl Buf) -> Result<Bytes, EncodingError> {
+    return hex_decode_with(src, &HexConfig::LOWER);
+}
+
+pub fn hex_decode_with(src: impl Buf, config: &HexConfig) -> Result<Bytes, EncodingError> {
+    let bytes = with_contiguous(src, |hex| codec().hex_decode_with(as_str(hex)?, config))?;
+    return Ok(Bytes::from(bytes));
+}
+
+pub fn base64_encode(src: impl Buf) -> Bytes {
+    return base64_encode_with(src, &Base64Config::STANDARD);
+}
+
+pub fn base64_encode_with(src: impl Buf, config: &Base64Config) -> Bytes {
+    return Bytes::from(with_contiguous(src, |bytes| codec().base64_encode_with(bytes, config)));
+}
+
+pub fn base64_decode(src: impl Buf) -> Result<Bytes, EncodingError> {
+    return base64_decode_with(src, &Base64Config::STANDARD);
+}
+
+pub fn base64_decode_with(src: impl Buf, config: &Base64Config) -> Result<Bytes, EncodingError> {
+    let bytes = with_contiguous(src, |b64| codec().base64_decode_with(as_str(b64)?, config))?;
+    return Ok(Bytes::from(bytes));
+}
+
+pub fn xor_bytes(buf1: impl Buf, buf2: impl Buf) -> Result<Bytes, EncodingError> {
+    let bytes = with_contiguous(buf1, |bytes1| {
+        with_contiguous(buf2, |bytes2| codec().xor_bytes(bytes1, bytes2))
+    })?;
+    return Ok(Bytes::from(bytes));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes::BytesMut;
+    use bytes::buf::BufExt;
+    use crate::encoding;
+
+    #[test]
+    fn test_buf_codecs() {
+        let plain = Bytes::from_static(b"I'm killing your brain like a poisonous mushroom");
+        let hex = encoding::hex_encode(&plain);
+        let b64 = encoding::base64_encode(&plain);
+
+        assert_eq!(hex_encode(plain.clone()), hex.as_bytes());
+        assert_eq!(base64_encode(&plain[..]), b64.as_bytes());
+        assert_eq!(hex_decode(Bytes::from(hex.clone())).unwrap(), plain);
+        assert_eq!(base64_decode(b64.as_bytes()).unwrap(), plain);
+
+        // frames appended to one BytesMut, then decoded back out of it
+        let mut frame = BytesMut::new();
+        hex_encode_into(&plain[..10], &mut frame);
+        frame.put_u8(b'|');
+        base64_encode_into_with(&plain[..], &mut frame, &Base64Config::URL_SAFE_NO_PAD);
+        assert_eq!(&frame[..21], format!("{}|", &hex[..20]).as_bytes());
+
+        let mut hex_part = frame.split_to(20);
+        frame.advance(1);
+        let mut out: Vec<u8> = Vec::new();
+        hex_decode_into(&mut hex_part, &mut out).unwrap();
+        assert!(!hex_part.has_remaining());
+        base64_decode_into_with(frame, &mut out, &Base64Config::URL_SAFE_NO_PAD).unwrap();
+        assert_eq!(&out[..10], &plain[..10]);
+        assert_eq!(&out[10..], &plain[..]);
+
+        // a chained buffer splits a hex pair and a base64 quantum across its halves
+        assert_eq!(hex_decode((&hex.as_bytes()[..7]).chain(&hex.as_bytes()[7..])).unwrap(), plain);
+        assert_eq!(base64_decode((&b64.as_bytes()[..5]).chain(&b64.as_bytes()[5..])).unwrap(), plain);
+
+        let key = Bytes::from(vec![0x20; plain.len()]);
+        let xored = xor_bytes(plain.clone(), key.clone()).unwrap();
+        assert_eq!(xored, encoding::xor_bytes(&plain, &key).unwrap());
+        let mut out = BytesMut::new();
+        xor_into(xored, key, &mut out).unwrap();
+        assert_eq!(out, plain);
+    }
+
+    #[test]
+    fn test_buf_errors() {
+        let mut out = BytesMut::new();
+        assert_eq!(hex_decode_into(&b"abcg"[..], &mut out), Err(EncodingError::InvalidDigit { byte: b'g', offset: 3 }));
+        assert_eq!(base64_decode_into(&b"QU*D"[..], &mut out), Err(EncodingError::InvalidDigit { byte: b'*', offset: 2 }));
+        assert_eq!(xor_into(&b"ab"[..], &b"abc"[..], &mut out), Err(EncodingError::LengthMismatch { left: 2, right: 3 }));
+        assert!(out.is_empty());
+
+        assert_eq!(hex_decode(&b"ab\xffd"[..]), Err(EncodingError::InvalidDigit { byte: 0xff, offset: 2 }));
+        assert_eq!(base64_decode(&b"QUJ"[..]), Err(EncodingError::InvalidLength { len: 3, multiple: 4 }));
+    }
+}
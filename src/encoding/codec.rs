@@ -0,0 +1,358 @@
+use std::env;
+use std::sync::OnceLock;
+use crate::encoding::{self, EncodingError, HexConfig, Base64Config};
+use crate::encoding::swar;
+#[cfg(feature = "simd")]
+use crate::encoding::simd;
+
+// one implementation of the hex, base64 and XOR routines.
+// encoding, encoding::swar and encoding::simd produce identical output for identical input,
+// so callers can hold a &dyn Codec and not care which one is behind it
+pub trait Codec: Sync {
+    fn backend(&self) -> Backend;
+
+    fn xor_bytes(&self, buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError>;
+    fn xor_assign(&self, dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError>;
+    fn xor_into(&self, out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError>;
+    fn xor_with_key(&self, bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError>;
+
+    fn hex_encode_with(&self, bytes: &[u8], config: &HexConfig) -> String;
+    fn hex_decode_with(&self, str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError>;
+
+    fn base64_encode_with(&self, bytes: &[u8], config: &Base64Config) -> String;
+    fn base64_decode_with(&self, str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError>;
+
+    fn hex_encode(&self, bytes: &[u8]) -> String {
+        return self.hex_encode_with(bytes, &HexConfig::LOWER);
+    }
+
+    fn hex_decode(&self, str: &str) -> Result<Vec<u8>, EncodingError> {
+        return self.hex_decode_with(str, &HexConfig::LOWER);
+    }
+
+    fn base64_encode(&self, bytes: &[u8]) -> String {
+        return self.base64_encode_with(bytes, &Base64Config::STANDARD);
+    }
+
+    fn base64_decode(&self, str: &str) -> Result<Vec<u8>, EncodingError> {
+        return self.base64_decode_with(str, &Base64Config::STANDARD);
+    }
+}
+
+// the scalar routines in encoding
+pub struct Scalar;
+
+impl Codec for Scalar {
+    fn backend(&self) -> Backend {
+        return Backend::Scalar;
+    }
+
+    fn xor_bytes(&self, buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> {
+        return encoding::xor_bytes(buf1, buf2);
+    }
+
+    fn xor_assign(&self, dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> {
+        return encoding::xor_assign(dst, src);
+    }
+
+    fn xor_into(&self, out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> {
+        return encoding::xor_into(out, buf1, buf2);
+    }
+
+    fn xor_with_key(&self, bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> {
+        return encoding::xor_with_key(bytes, key);
+    }
+
+    fn hex_encode_with(&self, bytes: &[u8], config: &HexConfig) -> String {
+        return encoding::hex_encode_with(bytes, config);
+    }
+
+    fn hex_decode_with(&self, str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> {
+        return encoding::hex_decode_with(str, config);
+    }
+
+    fn base64_encode_with(&self, bytes: &[u8], config: &Base64Config) -> String {
+        return encoding::base64_encode_with(bytes, config);
+    }
+
+    fn base64_decode_with(&self, str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+        return encoding::base64_decode_with(str, config);
+    }
+}
+
+// the word-at-a-time routines in encoding::swar, which build on stable
+pub struct Swar;
+
+impl Codec for Swar {
+    fn backend(&self) -> Backend {
+        return Backend::Swar;
+    }
+
+    fn xor_bytes(&self, buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> {
+        return swar::xor_bytes(buf1, buf2);
+    }
+
+    fn xor_assign(&self, dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> {
+        return swar::xor_assign(dst, src);
+    }
+
+    fn xor_into(&self, out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> {
+        return swar::xor_into(out, buf1, buf2);
+    }
+
+    fn xor_with_key(&self, bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> {
+        return swar::xor_with_key(bytes, key);
+    }
+
+    fn hex_encode_with(&self, bytes: &[u8], config: &HexConfig) -> String {
+        return swar::hex_encode_with(bytes, config);
+    }
+
+    fn hex_decode_with(&self, str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> {
+        return swar::hex_decode_with(str, config);
+    }
+
+    fn base64_encode_with(&self, bytes: &[u8], config: &Base64Config) -> String {
+        return swar::base64_encode_with(bytes, config);
+    }
+
+    fn base64_decode_with(&self, str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+        return swar::base64_decode_with(str, config);
+    }
+}
+
+// the routines in encoding::simd, N lanes wide. only built with the simd feature,
+// and only the widths that Backend names implement Codec
+#[cfg(feature = "simd")]
+pub struct Simd<const N: usize>;
+
+// call encoding::simd routine $f through a twin of it compiled with $features, when
+// $detected says the running CPU has them, or as compiled for the baseline target otherwise
+#[cfg(feature = "simd")]
+macro_rules! simd_dispatch {
+    ($f:path, ($($arg:ident: $ty:ty),*) -> $ret:ty, $features:literal, $detected:expr) => {{
+        #[target_feature(enable = $features)]
+        unsafe fn with_features($($arg: $ty),*) -> $ret {
+            return $f($($arg),*);
+        }
+
+        if $detected {
+            // safe: the CPU has every feature with_features was compiled for
+            return unsafe { with_features($($arg),*) };
+        }
+        return $f($($arg),*);
+    }};
+
+    ($f:path, ($($arg:ident: $ty:ty),*) -> $ret:ty) => {{
+        return $f($($arg),*);
+    }};
+}
+
+// Codec for Simd<$n>. the routines in encoding::simd are #[inline(always)], so the
+// twins simd_dispatch compiles with $features get e.g. vpshufb for swizzle_dyn
+#[cfg(feature = "simd")]
+macro_rules! simd_codec {
+    ($n:literal, $backend:ident $(, $features:literal, $detected:expr)?) => {
+        impl Codec for Simd<$n> {
+            fn backend(&self) -> Backend {
+                return Backend::$backend;
+            }
+
+            fn xor_bytes(&self, buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> {
+                simd_dispatch!(simd::xor_bytes::<$n>, (buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> $(, $features, $detected)?)
+            }
+
+            fn xor_assign(&self, dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> {
+                simd_dispatch!(simd::xor_assign::<$n>, (dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> $(, $features, $detected)?)
+            }
+
+            fn xor_into(&self, out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> {
+                simd_dispatch!(simd::xor_into::<$n>, (out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> $(, $features, $detected)?)
+            }
+
+            fn xor_with_key(&self, bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> {
+                simd_dispatch!(simd::xor_with_key::<$n>, (bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> $(, $features, $detected)?)
+            }
+
+            fn hex_encode_with(&self, bytes: &[u8], config: &HexConfig) -> String {
+                simd_dispatch!(simd::hex_encode_with::<$n>, (bytes: &[u8], config: &HexConfig) -> String $(, $features, $detected)?)
+            }
+
+            fn hex_decode_with(&self, str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> {
+                simd_dispatch!(simd::hex_decode_with::<$n>, (str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> $(, $features, $detected)?)
+            }
+
+            fn base64_encode_with(&self, bytes: &[u8], config: &Base64Config) -> String {
+                simd_dispatch!(simd::base64_encode_with::<$n>, (bytes: &[u8], config: &Base64Config) -> String $(, $features, $detected)?)
+            }
+
+            fn base64_decode_with(&self, str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+                simd_dispatch!(simd::base64_decode_with::<$n>, (str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> $(, $features, $detected)?)
+            }
+        }
+    };
+}
+
+// SSSE3 has pshufb, AVX2 256-bit vpshufb and AVX-512BW the 512-bit one
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+simd_codec!(16, Simd16, "ssse3", is_x86_feature_detected!("ssse3"));
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+simd_codec!(32, Simd32, "avx2", is_x86_feature_detected!("avx2"));
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+simd_codec!(64, Simd64, "avx512bw", is_x86_feature_detected!("avx512bw"));
+
+// NEON (with tbl) is 128 bits wide; wider vectors are split over several registers
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+simd_codec!(16, Simd16, "neon", std::arch::is_aarch64_feature_detected!("neon"));
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+simd_codec!(32, Simd32, "neon", std::arch::is_aarch64_feature_detected!("neon"));
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+simd_codec!(64, Simd64, "neon", std::arch::is_aarch64_feature_detected!("neon"));
+
+#[cfg(all(feature = "simd", not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+simd_codec!(16, Simd16);
+#[cfg(all(feature = "simd", not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+simd_codec!(32, Simd32);
+#[cfg(all(feature = "simd", not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+simd_codec!(64, Simd64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Scalar,
+    Swar, // u128 words, on any CPU
+    #[cfg(feature = "simd")]
+    Simd16, // SSSE3 / NEON: 128-bit vectors
+    #[cfg(feature = "simd")]
+    Simd32, // AVX2: 256-bit vectors
+    #[cfg(feature = "simd")]
+    Simd64, // AVX-512BW: 512-bit vectors
+}
+
+// set to scalar, swar, simd16, simd32 or simd64 to override detection,
+// e.g. to pin everything that goes through codec() to one backend while testing
+const BACKEND_ENV_VAR: &str = "CRYPTOPALS_BACKEND";
+
+impl Backend {
+    #[cfg(feature = "simd")]
+    pub const ALL: &'static [Backend] = &[Backend::Scalar, Backend::Swar, Backend::Simd16, Backend::Simd32, Backend::Simd64];
+    #[cfg(not(feature = "simd"))]
+    pub const ALL: &'static [Backend] = &[Backend::Scalar, Backend::Swar];
+
+    // the widest vectors the running CPU handles natively. the shuffles in
+    // encoding::simd need a byte shuffle instruction (pshufb / tbl) to pay off,
+    // so CPUs without one, and builds without the simd feature, get swar
+    pub fn detect() -> Backend {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx512bw") {
+                return Backend::Simd64;
+            } else if is_x86_feature_detected!("avx2") {
+                return Backend::Simd32;
+            } else if is_x86_feature_detected!("ssse3") {
+                return Backend::Simd16;
+            }
+        }
+
+        #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                return Backend::Simd16;
+            }
+        }
+
+        return Backend::Swar;
+    }
+
+    pub fn from_name(name: &str) -> Option<Backend> {
+        return match name.to_ascii_lowercase().as_str() {
+            "scalar" => Some(Backend::Scalar),
+            "swar" => Some(Backend::Swar),
+            #[cfg(feature = "simd")]
+            "simd16" => Some(Backend::Simd16),
+            #[cfg(feature = "simd")]
+            "simd32" => Some(Backend::Simd32),
+            #[cfg(feature = "simd")]
+            "simd64" => Some(Backend::Simd64),
+            _ => None,
+        };
+    }
+
+    // the backend CRYPTOPALS_BACKEND names: None if it is not set, or
+    // the name as the error if no backend goes by it
+    pub fn from_env() -> Option<Result<Backend, String>> {
+        let name = env::var(BACKEND_ENV_VAR).ok()?;
+        return Some(Backend::from_name(&name).ok_or(name));
+    }
+
+    pub fn codec(self) -> &'static dyn Codec {
+        return match self {
+            Backend::Scalar => &Scalar,
+            Backend::Swar => &Swar,
+            #[cfg(feature = "simd")]
+            Backend::Simd16 => &Simd::<16>,
+            #[cfg(feature = "simd")]
+            Backend::Simd32 => &Simd::<32>,
+            #[cfg(feature = "simd")]
+            Backend::Simd64 => &Simd::<64>,
+        };
+    }
+}
+
+// the codec for this process: the one named by CRYPTOPALS_BACKEND if set, otherwise
+// the best one for the running CPU. an unknown name falls back to the latter, so
+// callers that want to report it should check Backend::from_env. chosen once and cached
+pub fn codec() -> &'static dyn Codec {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+    let backend = BACKEND.get_or_init(|| {
+        match Backend::from_env() {
+            Some(Ok(backend)) => backend,
+            _ => Backend::detect(),
+        }
+    });
+    return backend.codec();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_backends_agree() {
+        let mut rng = rand::thread_rng();
+        let mut bytes1 = vec![0u8; 1000];
+        let mut bytes2 = vec![0u8; 1000];
+        rng.fill(&mut bytes1[..]);
+        rng.fill(&mut bytes2[..]);
+
+        let hex = encoding::hex_encode(&bytes1);
+        let b64 = encoding::base64_encode_with(&bytes1, &Base64Config::MIME);
+
+        for backend in Backend::ALL.iter() {
+            let codec = backend.codec();
+            assert_eq!(codec.backend(), *backend);
+
+            assert_eq!(codec.xor_bytes(&bytes1, &bytes2), encoding::xor_bytes(&bytes1, &bytes2));
+            let mut out = vec![0u8; bytes1.len()];
+            codec.xor_into(&mut out, &bytes1, &bytes2).unwrap();
+            codec.xor_assign(&mut out, &bytes2).unwrap();
+            assert_eq!(out, bytes1);
+            assert_eq!(codec.xor_with_key(&bytes1, b"ICE"), encoding::xor_with_key(&bytes1, b"ICE"));
+            assert_eq!(codec.hex_encode(&bytes1), hex);
+            assert_eq!(codec.hex_decode(&hex).unwrap(), bytes1);
+            assert_eq!(codec.base64_encode_with(&bytes1, &Base64Config::MIME), b64);
+            assert_eq!(codec.base64_decode_with(&b64, &Base64Config::MIME).unwrap(), bytes1);
+            assert_eq!(codec.base64_decode("QU*D"), Err(EncodingError::InvalidDigit { byte: b'*', offset: 2 }));
+        }
+
+        assert_eq!(Backend::from_name("SWAR"), Some(Backend::Swar));
+        assert_eq!(Backend::from_name("simd32").is_some(), cfg!(feature = "simd"));
+        assert_eq!(Backend::from_name("avx"), None);
+        assert!(Backend::ALL.contains(&codec().backend()));
+        match Backend::from_env() {
+            Some(Ok(backend)) => assert_eq!(codec().backend(), backend),
+            _ => assert_eq!(codec().backend(), Backend::detect()),
+        }
+    }
+}
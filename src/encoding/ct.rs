@@ -0,0 +1,281 @@
+use crate::encoding::{EncodingError, Base64Alphabet};
+
+// decoders for secret material (keys, PEM bodies) whose running time does not
+// depend on the characters being decoded. hex_val and base64_val branch on the
+// character, which leaks through timing; here every character goes through the
+// same arithmetic, and validity is accumulated in masks and only checked at the end.
+// the input length, padding length and base64 alphabet are treated as public
+
+// all ones if lo <= c <= hi, otherwise zero. c, lo and hi are bytes, so
+// both differences fit in an i32 and only their sign bits matter
+fn ct_in_range(c: i32, lo: i32, hi: i32) -> i32 {
+    return ((lo - 1 - c) & (c - hi - 1)) >> 31;
+}
+
+fn ct_eq(c: i32, v: u8) -> i32 {
+    return ct_in_range(c, v as i32, v as i32);
+}
+
+// widen a mask from ct_in_range to a usize mask
+fn ct_mask(mask: i32) -> usize {
+    return mask as isize as usize;
+}
+
+// the value of a hex digit, and a mask that is all ones if it is one
+fn ct_hex_val(c: u8) -> (i32, i32) {
+    let c = c as i32;
+    let digit = ct_in_range(c, 48, 57); // 0-9
+    let lower = ct_in_range(c, 97, 102); // a-f
+    let upper = ct_in_range(c, 65, 70); // A-F
+
+    let val = (digit & (c - 48)) | (lower & (c - 87)) | (upper & (c - 55));
+    return (val, digit | lower | upper);
+}
+
+// the value of a base64 digit, and a mask that is all ones if it is one
+fn ct_base64_val(c: u8, alphabet: Base64Alphabet) -> (i32, i32) {
+    let (c62, c63) = match alphabet {
+        Base64Alphabet::Standard => (b'+', b'/'),
+        Base64Alphabet::UrlSafe => (b'-', b'_'),
+    };
+
+    let c = c as i32;
+    let upper = ct_in_range(c, 65, 90); // A-Z
+    let lower = ct_in_range(c, 97, 122); // a-z
+    let digit = ct_in_range(c, 48, 57); // 0-9
+    let is62 = ct_eq(c, c62);
+    let is63 = ct_eq(c, c63);
+
+    let val = (upper & (c - 65)) | (lower & (c - 71)) | (digit & (c + 4)) | (is62 & 62) | (is63 & 63);
+    return (val, upper | lower | digit | is62 | is63);
+}
+
+// the first invalid character seen, tracked without branching on where it was
+struct CtFirstError {
+    seen: usize,
+    offset: usize,
+    byte: usize,
+}
+
+impl CtFirstError {
+    fn new() -> CtFirstError {
+        return CtFirstError { seen: 0, offset: 0, byte: 0 };
+    }
+
+    // bad is all ones if the character at offset is invalid
+    fn record(&mut self, bad: usize, offset: usize, byte: u8) {
+        let take = bad & !self.seen;
+        self.offset = (self.offset & !take) | (offset & take);
+        self.byte = (self.byte & !take) | (byte as usize & take);
+        self.seen |= bad;
+    }
+
+    // self if it has seen an error, otherwise other
+    fn or(&self, other: &CtFirstError) -> CtFirstError {
+        let keep = self.seen;
+        return CtFirstError {
+            seen: self.seen | other.seen,
+            offset: (self.offset & keep) | (other.offset & !keep),
+            byte: (self.byte & keep) | (other.byte & !keep),
+        };
+    }
+
+    // the only branch on the input's contents, taken once at the very end
+    fn result(&self) -> Result<(), EncodingError> {
+        if self.seen == 0 {
+            return Ok(());
+
+        } else if self.byte == b'=' as usize {
+            return Err(EncodingError::InvalidPadding { offset: self.offset });
+
+        } else {
+            return Err(EncodingError::InvalidDigit { byte: self.byte as u8, offset: self.offset });
+        }
+    }
+}
+
+// decode hex (either case, no separators) in constant time.
+// on invalid input, reports the same error as hex_decode
+pub fn ct_hex_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+    if !str_bytes.len().is_multiple_of(2) {
+        return Err(EncodingError::InvalidLength { len: str_bytes.len(), multiple: 2 });
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/2);
+    let mut first_error = CtFirstError::new();
+    for (i, pair) in str_bytes.chunks_exact(2).enumerate() {
+        let (hi, hi_ok) = ct_hex_val(pair[0]);
+        let (lo, lo_ok) = ct_hex_val(pair[1]);
+        first_error.record(!ct_mask(hi_ok), 2*i, pair[0]);
+        first_error.record(!ct_mask(lo_ok), 2*i+1, pair[1]);
+        bytes.push(((hi << 4) | lo) as u8);
+    }
+
+    first_error.result()?;
+    return Ok(bytes);
+}
+
+// decode padded standard base64 in constant time. line breaks are not skipped,
+// so strip them first (their positions are not secret)
+pub fn ct_base64_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    return ct_base64_decode_with(str, Base64Alphabet::Standard);
+}
+
+// decode padded base64 in the given alphabet in constant time.
+// on invalid input, reports the same error as base64_decode_with
+pub fn ct_base64_decode_with(str: &str, alphabet: Base64Alphabet) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+    if !str_bytes.len().is_multiple_of(4) {
+        return Err(EncodingError::InvalidLength { len: str_bytes.len(), multiple: 4 });
+    }
+
+    let n_quanta = str_bytes.len() / 4;
+    let mut bytes: Vec<u8> = Vec::with_capacity(n_quanta*3);
+    let mut first_error = CtFirstError::new();
+    let mut n_pad: usize = 0;
+    for (i, quantum) in str_bytes.chunks_exact(4).enumerate() {
+        let b = i * 4;
+
+        // "xx==" and "xxx=" are allowed in the final quantum, and '=' nowhere else
+        let last_quantum = ct_mask(-((i + 1 == n_quanta) as i32));
+        let pad3 = ct_mask(ct_eq(quantum[3] as i32, b'=')) & last_quantum;
+        let pad2 = ct_mask(ct_eq(quantum[2] as i32, b'=')) & pad3;
+        let pad_ok = [0, 0, pad2, pad3];
+
+        // like base64_decode_with, an invalid digit anywhere in a quantum
+        // is reported before misplaced padding in the same quantum
+        let mut vals = [0i32; 4];
+        let mut digit_error = CtFirstError::new();
+        let mut pad_error = CtFirstError::new();
+        for (j, &c) in quantum.iter().enumerate() {
+            let (val, ok) = ct_base64_val(c, alphabet);
+            let is_pad = ct_mask(ct_eq(c as i32, b'='));
+            vals[j] = val;
+            digit_error.record(!(ct_mask(ok) | is_pad), b+j, c);
+            pad_error.record(is_pad & !pad_ok[j], b+j, c);
+        }
+
+        let quantum_error = digit_error.or(&pad_error);
+        first_error.record(quantum_error.seen, quantum_error.offset, quantum_error.byte as u8);
+
+        bytes.push(((vals[0] << 2) | (vals[1] >> 4)) as u8);
+        bytes.push(((vals[1] << 4) | (vals[2] >> 2)) as u8);
+        bytes.push(((vals[2] << 6) | vals[3]) as u8);
+        n_pad = (pad3 & 1) + (pad2 & 1);
+    }
+
+    first_error.result()?;
+    bytes.truncate(bytes.len() - n_pad);
+    return Ok(bytes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoding::{self, Base64Config};
+    use rand::Rng;
+    use std::time::Instant;
+
+    #[test]
+    fn test_ct_matches_scalar() {
+        let mut rng = rand::thread_rng();
+
+        for len in 0..200 {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+
+            let hex = encoding::hex_encode(&bytes);
+            assert_eq!(ct_hex_decode(&hex).unwrap(), bytes);
+            assert_eq!(ct_hex_decode(&hex.to_ascii_uppercase()).unwrap(), bytes);
+
+            let b64 = encoding::base64_encode(&bytes);
+            assert_eq!(ct_base64_decode(&b64).unwrap(), bytes);
+            let b64_url = encoding::base64_encode_with(&bytes, &Base64Config::URL_SAFE);
+            assert_eq!(ct_base64_decode_with(&b64_url, Base64Alphabet::UrlSafe).unwrap(), bytes);
+
+            // corrupt one character and expect the same error as the scalar decoders
+            if len > 0 {
+                let mut corrupt = hex.clone().into_bytes();
+                let pos = rng.gen_range(0..corrupt.len());
+                corrupt[pos] = b"gG/:@`\n "[rng.gen_range(0..8)];
+                let corrupt = String::from_utf8(corrupt).unwrap();
+                assert_eq!(ct_hex_decode(&corrupt), encoding::hex_decode(&corrupt), "{:?}", corrupt);
+
+                let mut corrupt = b64.clone().into_bytes();
+                let pos = rng.gen_range(0..corrupt.len());
+                corrupt[pos] = b"=*-_\n.A/"[rng.gen_range(0..8)];
+                let corrupt = String::from_utf8(corrupt).unwrap();
+                assert_eq!(ct_base64_decode(&corrupt), encoding::base64_decode(&corrupt), "{:?}", corrupt);
+            }
+        }
+
+        assert_eq!(ct_hex_decode("abc"), Err(EncodingError::InvalidLength { len: 3, multiple: 2 }));
+        assert_eq!(ct_base64_decode("QUJ"), Err(EncodingError::InvalidLength { len: 3, multiple: 4 }));
+        assert_eq!(ct_base64_decode("Q==="), Err(EncodingError::InvalidPadding { offset: 1 }));
+        assert_eq!(ct_base64_decode("QQ==QUJD"), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(ct_base64_decode("QU=D"), Err(EncodingError::InvalidPadding { offset: 2 }));
+        assert_eq!(ct_base64_decode("QU*D=x"), Err(EncodingError::InvalidLength { len: 6, multiple: 4 }));
+        assert_eq!(ct_base64_decode("QU*DQU=D"), Err(EncodingError::InvalidDigit { byte: b'*', offset: 2 }));
+    }
+
+    // Welch's t statistic for the difference between the means of two samples
+    fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+        let mean = |xs: &[f64]| xs.iter().sum::<f64>() / xs.len() as f64;
+        let var = |xs: &[f64], m: f64| xs.iter().map(|x| (x - m) * (x - m)).sum::<f64>() / (xs.len() - 1) as f64;
+
+        let (mean_a, mean_b) = (mean(a), mean(b));
+        let (var_a, var_b) = (var(a, mean_a), var(b, mean_b));
+        return (mean_a - mean_b) / (var_a / a.len() as f64 + var_b / b.len() as f64).sqrt();
+    }
+
+    // time decode on inputs from two classes, picked in random order so that
+    // drift in the machine's speed hits both equally, and return the t statistic.
+    // like dudect, measurements above the 90th percentile are dropped as
+    // interrupts and scheduling noise
+    fn timing_t_statistic<T>(class_a: &str, class_b: &str, decode: impl Fn(&str) -> T) -> f64 {
+        const SAMPLES: usize = 20000;
+        const BATCH: usize = 8;
+
+        let mut rng = rand::thread_rng();
+        let mut times: Vec<(bool, f64)> = Vec::with_capacity(SAMPLES);
+        for _ in 0..SAMPLES {
+            let in_b = rng.gen::<bool>();
+            let input = if in_b { class_b } else { class_a };
+
+            let start = Instant::now();
+            for _ in 0..BATCH {
+                std::hint::black_box(decode(std::hint::black_box(input)));
+            }
+            times.push((in_b, start.elapsed().as_nanos() as f64));
+        }
+
+        let mut sorted: Vec<f64> = times.iter().map(|&(_, t)| t).collect();
+        sorted.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        let cutoff = sorted[SAMPLES * 9 / 10];
+
+        let a: Vec<f64> = times.iter().filter(|&&(in_b, t)| !in_b && t <= cutoff).map(|&(_, t)| t).collect();
+        let b: Vec<f64> = times.iter().filter(|&&(in_b, t)| in_b && t <= cutoff).map(|&(_, t)| t).collect();
+        return welch_t(&a, &b);
+    }
+
+    // the classes differ in which branch of hex_val / base64_val every character
+    // would take. |t| above 4.5 is dudect's threshold for a likely leak; we allow
+    // some headroom for a busy machine. wall-clock timing is too noisy for the
+    // normal test run, so this only runs with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_ct_timing() {
+        const T_THRESHOLD: f64 = 10.0;
+
+        let digits = "0123456789".repeat(26);
+        let letters = "abcdefABCDEF".repeat(22)[..260].to_string();
+        let t = timing_t_statistic(&digits, &letters, ct_hex_decode);
+        assert!(t.abs() < T_THRESHOLD, "ct_hex_decode: t = {}", t);
+
+        let b64_digits = "0123456789+/".repeat(22)[..260].to_string();
+        let b64_letters = "ABCDEFGHIJKLMNOPQRSTUVWXYZ".repeat(10);
+        let t = timing_t_statistic(&b64_digits, &b64_letters, ct_base64_decode);
+        assert!(t.abs() < T_THRESHOLD, "ct_base64_decode: t = {}", t);
+    }
+}
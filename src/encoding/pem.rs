@@ -0,0 +1,143 @@
+use crate::encoding::{self, EncodingError};
+
+// PEM armor (RFC 7468): base64 between "-----BEGIN label-----" and
+// "-----END label-----" lines, as used for keys and certificates
+
+// encoders wrap the base64 body at this many columns
+const PEM_LINE_WIDTH: usize = 64;
+
+const BEGIN_PREFIX: &str = "-----BEGIN ";
+const END_PREFIX: &str = "-----END ";
+const BOUNDARY_SUFFIX: &str = "-----";
+
+// armor bytes as a single PEM block, e.g. encode("PUBLIC KEY", &der)
+pub fn encode(label: &str, bytes: &[u8]) -> String {
+    let b64_str = encoding::base64_encode(bytes);
+
+    let mut pem_str: String = String::with_capacity(b64_str.len() + b64_str.len()/PEM_LINE_WIDTH + 2*label.len() + 40);
+    pem_str.push_str(&format!("{}{}{}\n", BEGIN_PREFIX, label, BOUNDARY_SUFFIX));
+    for line in b64_str.as_bytes().chunks(PEM_LINE_WIDTH) {
+        // base64 is ASCII, so any split of it is still UTF-8
+        pem_str.push_str(std::str::from_utf8(line).unwrap());
+        pem_str.push('\n');
+    }
+    pem_str.push_str(&format!("{}{}{}\n", END_PREFIX, label, BOUNDARY_SUFFIX));
+
+    return pem_str;
+}
+
+// the offset of the first line at or after pos that starts with prefix.
+// boundaries only count at the start of a line, not quoted in the middle of one
+fn find_line_start(str: &str, pos: usize, prefix: &str) -> Option<usize> {
+    let mut from = pos;
+    while let Some(i) = str[from..].find(prefix) {
+        let at = from + i;
+        if at == 0 || str.as_bytes()[at-1] == b'\n' {
+            return Some(at);
+        }
+        from = at + 1;
+    }
+    return None;
+}
+
+// the label of a boundary line starting at offset, e.g. "CERTIFICATE"
+// for "-----BEGIN CERTIFICATE-----". returns the label and the end of the line
+fn boundary_label<'a>(str: &'a str, offset: usize, prefix: &str) -> Result<(&'a str, usize), EncodingError> {
+    let line_end = match str[offset..].find('\n') {
+        Some(i) => offset + i,
+        None => str.len(),
+    };
+
+    let line = str[offset..line_end].trim_end();
+    if !line.ends_with(BOUNDARY_SUFFIX) || line.len() < prefix.len() + BOUNDARY_SUFFIX.len() {
+        return Err(EncodingError::InvalidBoundary { offset });
+    }
+
+    return Ok((&line[prefix.len() .. line.len() - BOUNDARY_SUFFIX.len()], line_end));
+}
+
+// decode every PEM block in str, in order, as (label, bytes) pairs. text outside
+// the blocks (such as the explanatory text openssl writes before certificates)
+// is ignored, and the body may be wrapped at any width with LF or CRLF
+pub fn decode(str: &str) -> Result<Vec<(String, Vec<u8>)>, EncodingError> {
+    let mut blocks: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut pos: usize = 0;
+    while let Some(begin) = find_line_start(str, pos, BEGIN_PREFIX) {
+        let (label, body_start) = boundary_label(str, begin, BEGIN_PREFIX)?;
+
+        let end = match find_line_start(str, body_start, END_PREFIX) {
+            Some(end) => end,
+            None => return Err(EncodingError::InvalidBoundary { offset: begin }),
+        };
+        let (end_label, end_line_end) = boundary_label(str, end, END_PREFIX)?;
+        if end_label != label {
+            return Err(EncodingError::InvalidBoundary { offset: end });
+        }
+
+        let body = &str.as_bytes()[body_start..end];
+        let positions: Vec<usize> = (0..body.len()).filter(|&j| !body[j].is_ascii_whitespace()).collect();
+        let b64_bytes: Vec<u8> = positions.iter().map(|&j| body[j]).collect();
+        if let Some(j) = b64_bytes.iter().position(|b| !b.is_ascii()) {
+            return Err(EncodingError::InvalidDigit { byte: b64_bytes[j], offset: body_start + positions[j] });
+        }
+        let b64_str = String::from_utf8(b64_bytes).unwrap();
+
+        let bytes = encoding::base64_decode(&b64_str).map_err(|e| match e.offset() {
+            Some(offset) => e.at(body_start + positions[offset]),
+            None => e,
+        })?;
+
+        blocks.push((label.to_string(), bytes));
+        pos = end_line_end;
+    }
+
+    return Ok(blocks);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pem_round_trip() {
+        let bytes: Vec<u8> = (0..100).collect();
+        let pem_str = encode("TEST DATA", &bytes);
+        assert_eq!(pem_str, "-----BEGIN TEST DATA-----\n\
+                             AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4v\n\
+                             MDEyMzQ1Njc4OTo7PD0+P0BBQkNERUZHSElKS0xNTk9QUVJTVFVWV1hZWltcXV5f\n\
+                             YGFiYw==\n\
+                             -----END TEST DATA-----\n");
+        assert_eq!(decode(&pem_str).unwrap(), vec![("TEST DATA".to_string(), bytes.clone())]);
+
+        assert_eq!(encode("EMPTY", b""), "-----BEGIN EMPTY-----\n-----END EMPTY-----\n");
+        assert_eq!(decode(&encode("EMPTY", b"")).unwrap(), vec![("EMPTY".to_string(), vec![])]);
+
+        // a certificate chain as openssl writes it: explanatory text, CRLF, several blocks
+        let chain = format!("subject=CN = leaf\r\n{}\r\nsubject=CN = root\r\n{}",
+                            encode("CERTIFICATE", &bytes[..40]).replace('\n', "\r\n"),
+                            encode("CERTIFICATE", &bytes[40..]));
+        assert_eq!(decode(&chain).unwrap(), vec![("CERTIFICATE".to_string(), bytes[..40].to_vec()),
+                                                 ("CERTIFICATE".to_string(), bytes[40..].to_vec())]);
+        assert_eq!(decode("no armor here").unwrap(), vec![]);
+
+        // boundaries quoted in the middle of a line are text, not armor
+        let quoted = format!("paste it after -----BEGIN TEST DATA-----\n{}", pem_str);
+        assert_eq!(decode(&quoted).unwrap(), vec![("TEST DATA".to_string(), bytes.clone())]);
+    }
+
+    #[test]
+    fn test_pem_errors() {
+        assert_eq!(decode("-----BEGIN KEY-----\nQUJD\n"), Err(EncodingError::InvalidBoundary { offset: 0 }));
+        assert_eq!(decode("-----BEGIN KEY\nQUJD\n-----END KEY-----\n"), Err(EncodingError::InvalidBoundary { offset: 0 }));
+        assert_eq!(decode("-----BEGIN KEY-----\nQUJD -----END KEY-----\n"), Err(EncodingError::InvalidBoundary { offset: 0 }));
+        assert_eq!(decode("-----BEGIN KEY-----\nQUJD\n-----END CERT-----\n"), Err(EncodingError::InvalidBoundary { offset: 25 }));
+
+        // offsets point into the whole file, past the line breaks that were skipped
+        assert_eq!(decode("-----BEGIN KEY-----\nQUJD\nQU*D\n-----END KEY-----\n"),
+                   Err(EncodingError::InvalidDigit { byte: b'*', offset: 27 }));
+        assert_eq!(decode("-----BEGIN KEY-----\nQUJD\nQU\u{e9}D\n-----END KEY-----\n"),
+                   Err(EncodingError::InvalidDigit { byte: 0xc3, offset: 27 }));
+        assert_eq!(decode("-----BEGIN KEY-----\nQUJ\n-----END KEY-----\n"),
+                   Err(EncodingError::InvalidLength { len: 3, multiple: 4 }));
+    }
+}
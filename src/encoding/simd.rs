@@ -0,0 +1,706 @@
+use std::simd::{Simd, Mask, Select};
+use std::simd::cmp::{SimdPartialEq, SimdPartialOrd};
+use std::simd::num::SimdUint;
+use crate::encoding::{self, EncodingError, HexConfig, Base64Alphabet, Base64Config};
+use crate::encoding::{base64_char_with, base64_val_with};
+
+// everything here is #[inline(always)], so that the entry points in codec that are
+// compiled with e.g. avx2 enabled compile these routines with it too
+
+// XOR two byte vectors
+#[inline(always)]
+pub fn xor_bytes<const N: usize>(buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    let mut out: Vec<u8> = buf1.to_vec();
+    xor_assign::<N>(&mut out, buf2)?;
+    return Ok(out);
+}
+
+// XOR src into dst in place, without allocating. from_slice and copy_to_slice
+// are unaligned loads and stores, so the slices can start anywhere
+#[inline(always)]
+pub fn xor_assign<const N: usize>(dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> {
+    if dst.len() != src.len() {
+        return Err(EncodingError::LengthMismatch { left: dst.len(), right: src.len() });
+    }
+
+    let mut dst_chunks = dst.chunks_exact_mut(N);
+    let mut src_chunks = src.chunks_exact(N);
+    for (dst_chunk, src_chunk) in (&mut dst_chunks).zip(&mut src_chunks) {
+        let chunk_res = Simd::<u8,N>::from_slice(dst_chunk) ^ Simd::<u8,N>::from_slice(src_chunk);
+        chunk_res.copy_to_slice(dst_chunk);
+    }
+
+    for (d, s) in dst_chunks.into_remainder().iter_mut().zip(src_chunks.remainder()) {
+        *d ^= s;
+    }
+
+    return Ok(());
+}
+
+// write buf1 XOR buf2 into out, without allocating. all three must be the same length
+#[inline(always)]
+pub fn xor_into<const N: usize>(out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> {
+    if buf1.len() != buf2.len() {
+        return Err(EncodingError::LengthMismatch { left: buf1.len(), right: buf2.len() });
+    }
+    if out.len() != buf1.len() {
+        return Err(EncodingError::LengthMismatch { left: out.len(), right: buf1.len() });
+    }
+
+    let mut out_chunks = out.chunks_exact_mut(N);
+    let mut chunks1 = buf1.chunks_exact(N);
+    let mut chunks2 = buf2.chunks_exact(N);
+    for ((out_chunk, chunk1), chunk2) in (&mut out_chunks).zip(&mut chunks1).zip(&mut chunks2) {
+        let chunk_res = Simd::<u8,N>::from_slice(chunk1) ^ Simd::<u8,N>::from_slice(chunk2);
+        chunk_res.copy_to_slice(out_chunk);
+    }
+
+    let remainders = chunks1.remainder().iter().zip(chunks2.remainder());
+    for (o, (b1, b2)) in out_chunks.into_remainder().iter_mut().zip(remainders) {
+        *o = b1 ^ b2;
+    }
+
+    return Ok(());
+}
+
+// xor_with_key repeats short keys to at least this many vectors, so most of
+// the work happens in xor_assign's vector loop rather than its scalar tail
+const XOR_KEY_BLOCK_VECTORS: usize = 16;
+
+// XOR bytes with key repeated end to end
+#[inline(always)]
+pub fn xor_with_key<const N: usize>(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    if key.is_empty() {
+        return Err(EncodingError::EmptyKey);
+    }
+
+    // a whole number of copies of the key, so every block of the input
+    // starts at the beginning of the key
+    let copies = (XOR_KEY_BLOCK_VECTORS * N).div_ceil(key.len());
+    let key_block: Vec<u8> = key.repeat(copies);
+
+    let mut result: Vec<u8> = bytes.to_vec();
+    for chunk in result.chunks_mut(key_block.len()) {
+        let n = chunk.len();
+        xor_assign::<N>(chunk, &key_block[..n])?;
+    }
+
+    return Ok(result);
+}
+
+// XOR bytes in place with a keystream, N keystream bytes at a time.
+// if the keystream ends early, the bytes past its end are left as they are
+#[inline(always)]
+pub fn xor_with_keystream<const N: usize>(bytes: &mut [u8], mut keystream: impl Iterator<Item=u8>) {
+    let mut key_buf = [0u8; N];
+    for chunk in bytes.chunks_mut(N) {
+        let mut n = 0;
+        for (k, b) in key_buf[..chunk.len()].iter_mut().zip(&mut keystream) {
+            *k = b;
+            n += 1;
+        }
+
+        if n == N {
+            let chunk_res = Simd::<u8,N>::from_slice(chunk) ^ Simd::<u8,N>::from_array(key_buf);
+            chunk_res.copy_to_slice(chunk);
+
+        } else {
+            for (b, k) in chunk.iter_mut().zip(&key_buf[..n]) {
+                *b ^= k;
+            }
+        }
+
+        if n < chunk.len() {
+            return;
+        }
+    }
+}
+
+// lane-index and shift tables for the hex and base64 shuffles below
+#[inline(always)]
+fn lane_table<const N: usize>(f: impl Fn(usize) -> u8) -> Simd<u8, N> {
+    return Simd::from_array(std::array::from_fn(f));
+}
+
+// a bitmask of the first n lanes, to ignore the lanes past the last whole
+// group when N is not a multiple of the group size
+#[inline(always)]
+fn lanes_below(n: usize) -> u64 {
+    return if n >= 64 { u64::MAX } else { (1 << n) - 1 };
+}
+
+// map N nibbles to hex digits at once
+#[inline(always)]
+fn hex_chars_simd<const N: usize>(nibbles: Simd<u8, N>, uppercase: bool) -> Simd<u8, N> {
+    let letter_offset = if uppercase { 55 } else { 87 };
+    let offset = nibbles.simd_lt(Simd::splat(10)).select(Simd::splat(48), Simd::splat(letter_offset));
+    return nibbles + offset;
+}
+
+// map N hex digits back to nibbles at once. lanes that are not hex digits are cleared in the returned mask
+#[inline(always)]
+fn hex_vals_simd<const N: usize>(chars: Simd<u8, N>) -> (Simd<u8, N>, Mask<i8, N>) {
+    let digit = chars.simd_ge(Simd::splat(b'0')) & chars.simd_le(Simd::splat(b'9'));
+    let upper = chars.simd_ge(Simd::splat(b'A')) & chars.simd_le(Simd::splat(b'F'));
+    let lower = chars.simd_ge(Simd::splat(b'a')) & chars.simd_le(Simd::splat(b'f'));
+
+    let mut vals = Simd::splat(0u8);
+    vals = digit.select(chars - Simd::splat(48), vals);
+    vals = upper.select(chars - Simd::splat(55), vals);
+    vals = lower.select(chars - Simd::splat(87), vals);
+    return (vals, digit | upper | lower);
+}
+
+// decode a hex string into raw bytes, N characters per iteration
+#[inline(always)]
+pub fn hex_decode<const N: usize>(str: &str) -> Result<Vec<u8>, EncodingError> {
+    if !str.len().is_multiple_of(2) {
+        return Err(EncodingError::InvalidLength { len: str.len(), multiple: 2 });
+    }
+
+    let str_bytes = str.as_bytes();
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/2);
+
+    // every iteration decodes the N/2 whole digit pairs among the N characters it
+    // loads. when N is odd the last lane is unused
+    let block_len = N / 2;
+    let chars_len = block_len * 2;
+    let mut i: usize = 0;
+    if block_len > 0 {
+        // output lane k is built from the digit pair at lanes 2k and 2k+1
+        let idx_hi = lane_table::<N>(|k| (2*k) as u8);
+        let idx_lo = lane_table::<N>(|k| (2*k + 1) as u8);
+
+        while i + N <= str_bytes.len() {
+            let chars = Simd::<u8, N>::from_slice(&str_bytes[i..i+N]);
+            let (vals, valid) = hex_vals_simd(chars);
+            let invalid = (!valid).to_bitmask() & lanes_below(chars_len);
+            if invalid != 0 {
+                let lane = invalid.trailing_zeros() as usize;
+                return Err(EncodingError::InvalidDigit { byte: str_bytes[i+lane], offset: i+lane });
+            }
+
+            let block = (vals.swizzle_dyn(idx_hi) << Simd::splat(4)) | vals.swizzle_dyn(idx_lo);
+            bytes.extend_from_slice(&block.as_array()[..block_len]);
+            i += chars_len;
+        }
+    }
+
+    // everything before i was validated as ASCII, so i is a char boundary
+    let tail = encoding::hex_decode(&str[i..]).map_err(|e| match e.offset() {
+        Some(offset) => e.at(i + offset),
+        None => e,
+    })?;
+    bytes.extend_from_slice(&tail);
+
+    return Ok(bytes);
+}
+
+// decode hex, optionally skipping the separators allowed by config.
+// only strict input is vectorized; separators go through the scalar decoder
+#[inline(always)]
+pub fn hex_decode_with<const N: usize>(str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> {
+    if config.skip_separators {
+        return encoding::hex_decode_with(str, config);
+    }
+
+    return hex_decode::<N>(str);
+}
+
+// encode raw bytes as a lowercase hex string
+#[inline(always)]
+pub fn hex_encode<const N: usize>(bytes: &[u8]) -> String {
+    return hex_encode_with::<N>(bytes, &HexConfig::LOWER);
+}
+
+// encode raw bytes as hex in the digit case chosen by config, N bytes per iteration
+#[inline(always)]
+pub fn hex_encode_with<const N: usize>(bytes: &[u8], config: &HexConfig) -> String {
+    let mut hex_bytes: Vec<u8> = Vec::with_capacity(bytes.len()*2);
+
+    let mut chunks = bytes.chunks_exact(N);
+    for chunk in &mut chunks {
+        let block = Simd::<u8, N>::from_slice(chunk);
+        let hi = hex_chars_simd(block >> Simd::splat(4), config.uppercase);
+        let lo = hex_chars_simd(block & Simd::splat(0b00001111), config.uppercase);
+
+        let (first, second) = hi.interleave(lo);
+        hex_bytes.extend_from_slice(first.as_array());
+        hex_bytes.extend_from_slice(second.as_array());
+    }
+
+    hex_bytes.extend_from_slice(encoding::hex_encode_with(chunks.remainder(), config).as_bytes());
+
+    // every character is a hex digit, so this is ASCII
+    return unsafe { String::from_utf8_unchecked(hex_bytes) };
+}
+
+// decode hex into a string with one char per byte.
+// bytes >= 0x80 become the chars U+0080..U+00FF, so use hex_decode for binary data
+#[inline(always)]
+pub fn hex_to_ascii_str<const N: usize>(str: &str) -> Result<String, EncodingError> {
+    return Ok(hex_decode::<N>(str)?.into_iter().map(|b| b as char).collect());
+}
+
+#[inline(always)]
+pub fn ascii_to_hex_str<const N: usize>(str: &str) -> String {
+    return hex_encode::<N>(str.as_bytes());
+}
+
+// re-encode a hex string as padded base64
+#[inline(always)]
+pub fn hex_to_base64<const N: usize>(str: &str) -> Result<String, EncodingError> {
+    return Ok(bytes_to_base64::<N>(&hex_decode::<N>(str)?));
+}
+
+// map N sextets to base64 characters at once
+#[inline(always)]
+fn base64_chars_simd<const N: usize>(sextets: Simd<u8, N>, alphabet: Base64Alphabet) -> Simd<u8, N> {
+    let char62 = base64_char_with(62, alphabet).unwrap() as u8;
+    let char63 = base64_char_with(63, alphabet).unwrap() as u8;
+
+    // add the distance from the first sextet of each range to its first character
+    let mut offset = Simd::splat(65u8); // A-Z
+    offset = sextets.simd_ge(Simd::splat(26)).select(Simd::splat(71), offset); // a-z
+    offset = sextets.simd_ge(Simd::splat(52)).select(Simd::splat(4u8.wrapping_neg()), offset); // 0-9
+    offset = sextets.simd_eq(Simd::splat(62)).select(Simd::splat(char62.wrapping_sub(62)), offset);
+    offset = sextets.simd_eq(Simd::splat(63)).select(Simd::splat(char63.wrapping_sub(63)), offset);
+    return sextets + offset;
+}
+
+// map N base64 characters back to sextets at once.
+// lanes outside the alphabet, including '=', are cleared in the returned mask
+#[inline(always)]
+fn base64_vals_simd<const N: usize>(chars: Simd<u8, N>, alphabet: Base64Alphabet) -> (Simd<u8, N>, Mask<i8, N>) {
+    let char62 = base64_char_with(62, alphabet).unwrap() as u8;
+    let char63 = base64_char_with(63, alphabet).unwrap() as u8;
+
+    let upper = chars.simd_ge(Simd::splat(b'A')) & chars.simd_le(Simd::splat(b'Z'));
+    let lower = chars.simd_ge(Simd::splat(b'a')) & chars.simd_le(Simd::splat(b'z'));
+    let digit = chars.simd_ge(Simd::splat(b'0')) & chars.simd_le(Simd::splat(b'9'));
+    let is62 = chars.simd_eq(Simd::splat(char62));
+    let is63 = chars.simd_eq(Simd::splat(char63));
+
+    let mut vals = Simd::splat(0u8);
+    vals = upper.select(chars - Simd::splat(65), vals);
+    vals = lower.select(chars - Simd::splat(71), vals);
+    vals = digit.select(chars + Simd::splat(4), vals);
+    vals = is62.select(Simd::splat(62), vals);
+    vals = is63.select(Simd::splat(63), vals);
+    return (vals, upper | lower | digit | is62 | is63);
+}
+
+// padded base64 in the given alphabet, N output characters per iteration
+#[inline(always)]
+fn base64_encode_alphabet<const N: usize>(bytes: &[u8], alphabet: Base64Alphabet) -> String {
+    let mut b64_bytes: Vec<u8> = Vec::with_capacity(bytes.len().div_ceil(3)*4);
+
+    // every iteration turns the 3N/4 input bytes of N/4 whole groups into 4(N/4)
+    // characters, but loads N bytes, so the last few groups (and any padding) are
+    // left to the scalar encoder. when N is not a multiple of 4 the last lanes are unused
+    let block_len = N / 4 * 3;
+    let chars_len = N / 4 * 4;
+    let mut i: usize = 0;
+    if block_len > 0 {
+        // output lane k is sextet k%4 of group k/4, which sits
+        // inside the 16 bits formed by two adjacent input bytes
+        let idx_hi = lane_table::<N>(|k| (k/4*3 + (k%4)/2) as u8);
+        let idx_lo = idx_hi + Simd::splat(1);
+        let shifts: Simd<u16, N> = Simd::from_array(std::array::from_fn(|k| [10, 4, 6, 0][k%4]));
+
+        while i + N <= bytes.len() {
+            let block = Simd::<u8, N>::from_slice(&bytes[i..i+N]);
+            let hi = block.swizzle_dyn(idx_hi).cast::<u16>();
+            let lo = block.swizzle_dyn(idx_lo).cast::<u16>();
+            let sextets = (((hi << Simd::splat(8)) | lo) >> shifts) & Simd::splat(63);
+
+            let chars = base64_chars_simd(sextets.cast::<u8>(), alphabet);
+            b64_bytes.extend_from_slice(&chars.as_array()[..chars_len]);
+            i += block_len;
+        }
+    }
+
+    let scalar_config = Base64Config { alphabet, ..Base64Config::STANDARD };
+    b64_bytes.extend_from_slice(encoding::base64_encode_with(&bytes[i..], &scalar_config).as_bytes());
+
+    // every character came from the base64 alphabet, so this is ASCII
+    return unsafe { String::from_utf8_unchecked(b64_bytes) };
+}
+
+// the error the scalar decoder reports for the first quantum of block that
+// contains a character outside the alphabet. block is never the end of the input,
+// so any '=' in it is misplaced
+#[inline(always)]
+fn base64_block_error(block: &[u8], start: usize, invalid: u64, config: &Base64Config) -> EncodingError {
+    let q = (invalid.trailing_zeros() as usize) / 4 * 4;
+    let mut padding: Option<usize> = None;
+    for (j, &c) in block[q..q+4].iter().enumerate() {
+        let offset = start + q + j;
+        if c == b'=' {
+            if !config.allows_padding() {
+                return EncodingError::InvalidPadding { offset };
+            }
+            padding = padding.or(Some(offset));
+
+        } else if base64_val_with(c as char, config.alphabet).is_err() {
+            return EncodingError::InvalidDigit { byte: c, offset };
+        }
+    }
+
+    return EncodingError::InvalidPadding { offset: padding.unwrap() };
+}
+
+// decode base64 without whitespace, N characters per iteration
+#[inline(always)]
+fn base64_decode_unwrapped<const N: usize>(str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+
+    // unpadded input may end in a 2 or 3 character quantum, but never a single character
+    let len_ok =
+        if config.requires_padding() {
+            str_bytes.len().is_multiple_of(4)
+        } else {
+            str_bytes.len() % 4 != 1
+        };
+    if !len_ok {
+        return Err(EncodingError::InvalidLength { len: str_bytes.len(), multiple: 4 });
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/4*3 + 2);
+
+    // the final quantum may hold padding, so it is always left to the scalar decoder.
+    // every iteration decodes the N/4 whole quanta among the N characters it loads
+    let block_len = N / 4 * 3;
+    let chars_len = N / 4 * 4;
+    let mut i: usize = 0;
+    if block_len > 0 {
+        // output lane k is byte k%3 of quantum k/3, built from two adjacent sextets
+        let idx_hi = lane_table::<N>(|k| (k/3*4 + k%3) as u8);
+        let idx_lo = idx_hi + Simd::splat(1);
+        let shl = lane_table::<N>(|k| [2, 4, 6][k%3]);
+        let shr = lane_table::<N>(|k| [4, 2, 0][k%3]);
+
+        while i + N + 4 <= str_bytes.len() {
+            let chars = Simd::<u8, N>::from_slice(&str_bytes[i..i+N]);
+            let (vals, valid) = base64_vals_simd(chars, config.alphabet);
+            let invalid = (!valid).to_bitmask() & lanes_below(chars_len);
+            if invalid != 0 {
+                return Err(base64_block_error(&str_bytes[i..i+chars_len], i, invalid, config));
+            }
+
+            let hi = vals.swizzle_dyn(idx_hi);
+            let lo = vals.swizzle_dyn(idx_lo);
+            let block = (hi << shl) | (lo >> shr);
+
+            bytes.extend_from_slice(&block.as_array()[..block_len]);
+            i += chars_len;
+        }
+    }
+
+    // everything before i was validated as ASCII, so i is a char boundary
+    let tail = encoding::base64_decode_with(&str[i..], config).map_err(|e| match e.offset() {
+        Some(offset) => e.at(i + offset),
+        None => e,
+    })?;
+    bytes.extend_from_slice(&tail);
+
+    return Ok(bytes);
+}
+
+// decode a padded base64 string into raw bytes
+#[inline(always)]
+pub fn base64_decode<const N: usize>(str: &str) -> Result<Vec<u8>, EncodingError> {
+    return base64_decode_with::<N>(str, &Base64Config::STANDARD);
+}
+
+// decode base64 in the variant described by config
+#[inline(always)]
+pub fn base64_decode_with<const N: usize>(str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+    if !config.skips_whitespace() {
+        return base64_decode_unwrapped::<N>(str, config);
+    }
+
+    // squeeze out whitespace, then move error offsets back to the original input.
+    // whitespace is never part of a multi-byte character, so data stays valid UTF-8
+    let str_bytes = str.as_bytes();
+    let positions: Vec<usize> = (0..str_bytes.len()).filter(|&i| !str_bytes[i].is_ascii_whitespace()).collect();
+    let data = String::from_utf8(positions.iter().map(|&i| str_bytes[i]).collect()).unwrap();
+
+    let unwrapped = Base64Config { line_wrap: None, ..*config };
+    return base64_decode_unwrapped::<N>(&data, &unwrapped).map_err(|e| match e {
+        EncodingError::InvalidLength { .. } => e,
+        _ => e.at(positions[e.offset().unwrap()]),
+    });
+}
+
+// encode raw bytes as base64. inputs whose length is not a multiple of 3
+// end in a short quantum, which is padded with "==" (1 byte left) or "=" (2 bytes left)
+#[inline(always)]
+pub fn bytes_to_base64<const N: usize>(bytes: &[u8]) -> String {
+    return base64_encode_alphabet::<N>(bytes, Base64Alphabet::Standard);
+}
+
+// encode raw bytes as base64; the byte-oriented twin of base64_decode
+#[inline(always)]
+pub fn base64_encode<const N: usize>(bytes: &[u8]) -> String {
+    return bytes_to_base64::<N>(bytes);
+}
+
+// encode raw bytes as base64 in the variant described by config
+#[inline(always)]
+pub fn base64_encode_with<const N: usize>(bytes: &[u8], config: &Base64Config) -> String {
+    let mut b64_str = base64_encode_alphabet::<N>(bytes, config.alphabet);
+
+    if !config.pad {
+        b64_str.truncate(b64_str.trim_end_matches('=').len());
+    }
+
+    return match config.line_wrap {
+        Some(width) if width > 0 => {
+            let lines: Vec<&str> = b64_str.as_bytes()
+                .chunks(width)
+                .map(|line| std::str::from_utf8(line).unwrap())
+                .collect();
+            lines.join("\r\n")
+        },
+
+        _ => b64_str,
+    };
+}
+
+// decode base64 into a string with one char per byte; see hex_to_ascii_str
+#[inline(always)]
+pub fn base64_to_ascii_str<const N: usize>(str: &str) -> Result<String, EncodingError> {
+    return Ok(base64_decode::<N>(str)?.into_iter().map(|b| b as char).collect());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_xor_bytes1() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes1 = [0u8; 1000000];
+        let mut bytes2 = [0u8; 1000000];
+        rng.fill(&mut bytes1[..]);
+        rng.fill(&mut bytes2[..]);
+
+        let mut n: usize = 0;
+        for _ in 0..500 {
+            let res = xor_bytes::<64>(&bytes1, &bytes2).unwrap();
+            n += res.len();
+        }
+        assert_eq!(n, 500 * bytes1.len());
+    }
+
+    // compare with encoding::test::test_xor_into_speed
+    #[test]
+    fn test_xor_into_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes1 = vec![0u8; 1000000];
+        let mut bytes2 = vec![0u8; 1000000];
+        rng.fill(&mut bytes1[..]);
+        rng.fill(&mut bytes2[..]);
+
+        let mut out = vec![0u8; bytes1.len()];
+        for _ in 0..500 {
+            xor_into::<64>(&mut out, &bytes1, &bytes2).unwrap();
+        }
+        assert_eq!(out, encoding::xor_bytes(&bytes1, &bytes2).unwrap());
+    }
+
+    fn check_xor_matches_scalar<const N: usize>() {
+        let mut rng = rand::thread_rng();
+        let mut bytes1 = vec![0u8; 300];
+        let mut bytes2 = vec![0u8; 300];
+        rng.fill(&mut bytes1[..]);
+        rng.fill(&mut bytes2[..]);
+
+        // slices starting at every offset within a vector, so that loads and stores are unaligned
+        for start in 0..N.min(bytes1.len()) {
+            for len in [0, 1, N-1, N, N+1, 3*N+5, 300-start] {
+                let len = len.min(300-start);
+                let (a, b) = (&bytes1[start..start+len], &bytes2[start..start+len]);
+                let expected = encoding::xor_bytes(a, b).unwrap();
+
+                assert_eq!(xor_bytes::<N>(a, b).unwrap(), expected);
+
+                let mut out = vec![0u8; len + start];
+                xor_into::<N>(&mut out[start..], a, b).unwrap();
+                assert_eq!(out[start..], expected[..]);
+
+                let mut dst = bytes1.clone();
+                xor_assign::<N>(&mut dst[start..start+len], b).unwrap();
+                assert_eq!(dst[start..start+len], expected[..]);
+                assert_eq!(dst[..start], bytes1[..start]);
+                assert_eq!(dst[start+len..], bytes1[start+len..]);
+            }
+        }
+
+        let mut out = [0u8; 3];
+        assert_eq!(xor_assign::<N>(&mut out, &bytes1[..4]), Err(EncodingError::LengthMismatch { left: 3, right: 4 }));
+        assert_eq!(xor_into::<N>(&mut out, &bytes1[..3], &bytes2[..4]), Err(EncodingError::LengthMismatch { left: 3, right: 4 }));
+        assert_eq!(xor_into::<N>(&mut out, &bytes1[..4], &bytes2[..4]), Err(EncodingError::LengthMismatch { left: 3, right: 4 }));
+    }
+
+    #[test]
+    fn test_xor_matches_scalar() {
+        check_xor_matches_scalar::<2>();
+        check_xor_matches_scalar::<16>();
+        check_xor_matches_scalar::<32>();
+        check_xor_matches_scalar::<64>();
+    }
+
+    fn check_xor_with_key_matches_scalar<const N: usize>() {
+        let mut rng = rand::thread_rng();
+
+        for len in [0, 1, N-1, N, N+1, 17*N+3, 1000] {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+
+            for key_len in [1, 2, 3, N-1, N, N+1, 29, 1000, 2000] {
+                let mut key = vec![0u8; key_len];
+                rng.fill(&mut key[..]);
+                let expected = encoding::xor_with_key(&bytes, &key).unwrap();
+                assert_eq!(xor_with_key::<N>(&bytes, &key).unwrap(), expected);
+
+                let mut streamed = bytes.clone();
+                xor_with_keystream::<N>(&mut streamed, key.iter().cloned().cycle());
+                assert_eq!(streamed, expected);
+
+                let mut streamed = bytes.clone();
+                encoding::xor_with_keystream(&mut streamed, key.iter().cloned());
+                let mut streamed_simd = bytes.clone();
+                xor_with_keystream::<N>(&mut streamed_simd, key.iter().cloned());
+                assert_eq!(streamed_simd, streamed);
+            }
+        }
+
+        assert_eq!(xor_with_key::<N>(b"abc", b""), Err(EncodingError::EmptyKey));
+    }
+
+    #[test]
+    fn test_xor_with_key_matches_scalar() {
+        check_xor_with_key_matches_scalar::<2>();
+        check_xor_with_key_matches_scalar::<16>();
+        check_xor_with_key_matches_scalar::<32>();
+        check_xor_with_key_matches_scalar::<64>();
+    }
+
+    fn check_base64_matches_scalar<const N: usize>() {
+        let mut rng = rand::thread_rng();
+        let configs = [Base64Config::STANDARD, Base64Config::STANDARD_NO_PAD,
+                       Base64Config::URL_SAFE, Base64Config::URL_SAFE_NO_PAD,
+                       Base64Config::MIME, Base64Config::STRICT, Base64Config::LENIENT];
+
+        for len in 0..300 {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+
+            for config in configs.iter() {
+                let encoded = encoding::base64_encode_with(&bytes, config);
+                assert_eq!(base64_encode_with::<N>(&bytes, config), encoded);
+                assert_eq!(base64_decode_with::<N>(&encoded, config).unwrap(), bytes);
+
+                // corrupt one character and expect the same error as the scalar decoder
+                if !encoded.is_empty() {
+                    let mut corrupt = encoded.clone().into_bytes();
+                    let pos = rng.gen_range(0..corrupt.len());
+                    corrupt[pos] = b"=*\n-/\0"[rng.gen_range(0..6)];
+                    let corrupt = String::from_utf8(corrupt).unwrap();
+                    assert_eq!(base64_decode_with::<N>(&corrupt, config),
+                               encoding::base64_decode_with(&corrupt, config),
+                               "{:?} {:?}", corrupt, config);
+                }
+            }
+        }
+
+        let non_ascii = "QUJDQUJDQUJDQUJDQUJDQUJDQUJDQUJDQUJDQUJD\u{e9}UJD";
+        assert_eq!(base64_decode::<N>(non_ascii), encoding::base64_decode(non_ascii));
+    }
+
+    #[test]
+    fn test_base64_matches_scalar() {
+        check_base64_matches_scalar::<4>();
+        check_base64_matches_scalar::<6>();
+        check_base64_matches_scalar::<12>();
+        check_base64_matches_scalar::<16>();
+        check_base64_matches_scalar::<32>();
+        check_base64_matches_scalar::<64>();
+    }
+
+    // compare with encoding::test::test_base64_speed
+    #[test]
+    fn test_base64_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000000];
+        rng.fill(&mut bytes[..]);
+
+        for _ in 0..20 {
+            let encoded = base64_encode::<64>(&bytes);
+            assert_eq!(base64_decode::<64>(&encoded).unwrap().len(), bytes.len());
+        }
+    }
+
+    fn check_hex_matches_scalar<const N: usize>() {
+        let mut rng = rand::thread_rng();
+
+        for len in 0..300 {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+
+            for config in [HexConfig::LOWER, HexConfig::UPPER].iter() {
+                let encoded = encoding::hex_encode_with(&bytes, config);
+                assert_eq!(hex_encode_with::<N>(&bytes, config), encoded);
+                assert_eq!(hex_decode::<N>(&encoded).unwrap(), bytes);
+
+                // corrupt one character and expect the same error as the scalar decoder
+                if !encoded.is_empty() {
+                    let mut corrupt = encoded.clone().into_bytes();
+                    let pos = rng.gen_range(0..corrupt.len());
+                    corrupt[pos] = b"gG/:@`\n "[rng.gen_range(0..8)];
+                    let corrupt = String::from_utf8(corrupt).unwrap();
+                    assert_eq!(hex_decode::<N>(&corrupt), encoding::hex_decode(&corrupt), "{:?}", corrupt);
+                }
+            }
+        }
+
+        let non_ascii = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff\u{e9}";
+        assert_eq!(hex_decode::<N>(non_ascii), encoding::hex_decode(non_ascii));
+        assert_eq!(hex_decode::<N>(&non_ascii[..64]), encoding::hex_decode(&non_ascii[..64]));
+        assert_eq!(hex_decode::<N>("00010203"), Ok(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_hex_matches_scalar() {
+        check_hex_matches_scalar::<2>();
+        check_hex_matches_scalar::<3>();
+        check_hex_matches_scalar::<6>();
+        check_hex_matches_scalar::<16>();
+        check_hex_matches_scalar::<32>();
+        check_hex_matches_scalar::<64>();
+    }
+
+    // compare with encoding::test::test_hex_speed
+    #[test]
+    fn test_hex_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000000];
+        rng.fill(&mut bytes[..]);
+
+        for _ in 0..20 {
+            let encoded = hex_encode::<64>(&bytes);
+            assert_eq!(hex_decode::<64>(&encoded).unwrap().len(), bytes.len());
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use std::cmp;
+use std::io::{self, Read, Write};
+use crate::encoding::*;
+
+// size of the chunks the decoders pull from their underlying reader
+const READ_CHUNK_SIZE: usize = 8192;
+
+// encoding errors surface through the io traits as InvalidData.
+// offsets count characters from the start of the stream.
+// the decoders report the first problem they reach, so for malformed input
+// they can return a different error than the one-shot functions, which check lengths first
+fn invalid_data(e: EncodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+// hex-encodes everything written to it and passes it on to the inner writer
+pub struct HexEncoder<W: Write> {
+    inner: W,
+    config: HexConfig,
+}
+
+impl<W: Write> HexEncoder<W> {
+    pub fn new(inner: W) -> HexEncoder<W> {
+        return HexEncoder::with_config(inner, HexConfig::LOWER);
+    }
+
+    pub fn with_config(inner: W, config: HexConfig) -> HexEncoder<W> {
+        return HexEncoder { inner, config };
+    }
+
+    pub fn into_inner(self) -> W {
+        return self.inner;
+    }
+}
+
+impl<W: Write> Write for HexEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write_all(hex_encode_with(buf, &self.config).as_bytes())?;
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+// reads hex from the inner reader and yields the decoded bytes
+pub struct HexDecoder<R: Read> {
+    inner: R,
+    config: HexConfig,
+
+    // first digit of a byte whose second digit is still to come, with its value
+    pending: Option<(u8, u8)>,
+
+    // offset of the next character read from inner, and how many digits went into decoded bytes
+    offset: usize,
+    digits: usize,
+
+    out: Vec<u8>,
+    out_pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> HexDecoder<R> {
+    pub fn new(inner: R) -> HexDecoder<R> {
+        return HexDecoder::with_config(inner, HexConfig::LOWER);
+    }
+
+    // only config.skip_separators matters when decoding
+    pub fn with_config(inner: R, config: HexConfig) -> HexDecoder<R> {
+        return HexDecoder {
+            inner, config,
+            pending: None,
+            offset: 0, digits: 0,
+            out: Vec::new(), out_pos: 0, eof: false,
+        };
+    }
+
+    pub fn into_inner(self) -> R {
+        return self.inner;
+    }
+
+    fn decode_chunk(&mut self, chunk: &[u8]) -> Result<(), EncodingError> {
+        for &c in chunk {
+            let offset = self.offset;
+            self.offset += 1;
+            match self.pending {
+                None => {
+                    if self.config.skip_separators && (c == b':' || c.is_ascii_whitespace()) {
+                        continue;
+                    }
+
+                    let val = hex_val(c as char).map_err(|e| e.at(offset))?;
+                    self.pending = Some((c, val));
+                },
+
+                Some((first, first_val)) => {
+                    // a "0x" prefix looks like a byte until its second character arrives
+                    if self.config.skip_separators && first == b'0' && (c == b'x' || c == b'X') {
+                        self.pending = None;
+                        continue;
+                    }
+
+                    let val = hex_val(c as char).map_err(|e| e.at(offset))?;
+                    self.out.push(((first_val << 4) & 0b11110000) | val);
+                    self.digits += 2;
+                    self.pending = None;
+                },
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn decode_end(&mut self) -> Result<(), EncodingError> {
+        if self.pending.is_some() {
+            let len = if self.config.skip_separators { self.digits + 1 } else { self.offset };
+            return Err(EncodingError::InvalidLength { len, multiple: 2 });
+        }
+
+        return Ok(());
+    }
+}
+
+impl<R: Read> Read for HexDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos == self.out.len() && !self.eof {
+            self.out.clear();
+            self.out_pos = 0;
+
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.eof = true;
+                self.decode_end().map_err(invalid_data)?;
+            } else {
+                self.decode_chunk(&chunk[..n]).map_err(invalid_data)?;
+            }
+        }
+
+        let n = cmp::min(buf.len(), self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos .. self.out_pos+n]);
+        self.out_pos += n;
+        return Ok(n);
+    }
+}
+
+// base64-encodes everything written to it and passes it on to the inner writer.
+// the final quantum (and its padding) is only written by finish, or when the encoder is dropped
+pub struct Base64Encoder<W: Write> {
+    inner: Option<W>,
+    config: Base64Config,
+
+    // trailing bytes of the last write that do not make up a full 3-byte group
+    leftover: Vec<u8>,
+
+    // characters written to the current line, for line wrapping
+    column: usize,
+}
+
+impl<W: Write> Base64Encoder<W> {
+    pub fn new(inner: W) -> Base64Encoder<W> {
+        return Base64Encoder::with_config(inner, Base64Config::STANDARD);
+    }
+
+    pub fn with_config(inner: W, config: Base64Config) -> Base64Encoder<W> {
+        return Base64Encoder { inner: Some(inner), config, leftover: Vec::with_capacity(3), column: 0 };
+    }
+
+    // write out the final quantum and hand back the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_final()?;
+        return Ok(self.inner.take().unwrap());
+    }
+
+    fn write_groups(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let unwrapped = Base64Config { line_wrap: None, ..self.config };
+        let encoded = base64_encode_with(bytes, &unwrapped);
+        return self.write_wrapped(encoded.as_bytes());
+    }
+
+    fn write_wrapped(&mut self, encoded: &[u8]) -> io::Result<()> {
+        let inner = self.inner.as_mut().unwrap();
+        let width = match self.config.line_wrap {
+            Some(width) if width > 0 => width,
+            _ => return inner.write_all(encoded),
+        };
+
+        // line breaks go before a character, never after the last one
+        let mut rest = encoded;
+        while !rest.is_empty() {
+            if self.column == width {
+                inner.write_all(b"\r\n")?;
+                self.column = 0;
+            }
+
+            let n = cmp::min(width - self.column, rest.len());
+            inner.write_all(&rest[..n])?;
+            self.column += n;
+            rest = &rest[n..];
+        }
+
+        return Ok(());
+    }
+
+    fn write_final(&mut self) -> io::Result<()> {
+        if !self.leftover.is_empty() {
+            let leftover = std::mem::take(&mut self.leftover);
+            self.write_groups(&leftover)?;
+        }
+
+        return self.inner.as_mut().unwrap().flush();
+    }
+}
+
+impl<W: Write> Write for Base64Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut input = buf;
+
+        // top up the group left over from the previous write first
+        if !self.leftover.is_empty() {
+            let n = cmp::min(3 - self.leftover.len(), input.len());
+            self.leftover.extend_from_slice(&input[..n]);
+            input = &input[n..];
+
+            if self.leftover.len() < 3 {
+                return Ok(buf.len());
+            }
+
+            let group = std::mem::take(&mut self.leftover);
+            self.write_groups(&group)?;
+        }
+
+        let full = input.len() / 3 * 3;
+        self.write_groups(&input[..full])?;
+        self.leftover.extend_from_slice(&input[full..]);
+
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.as_mut().unwrap().flush();
+    }
+}
+
+impl<W: Write> Drop for Base64Encoder<W> {
+    fn drop(&mut self) {
+        // like BufWriter, errors on drop are ignored; call finish to see them
+        if self.inner.is_some() {
+            let _ = self.write_final();
+        }
+    }
+}
+
+// reads base64 from the inner reader and yields the decoded bytes
+pub struct Base64Decoder<R: Read> {
+    inner: R,
+    config: Base64Config,
+
+    // data characters of an incomplete quantum, and their offsets in the stream
+    quantum: Vec<u8>,
+    quantum_offsets: Vec<usize>,
+
+    // offset of the next character read from inner, and how many of them carried data
+    offset: usize,
+    chars: usize,
+
+    // once a padded quantum has been decoded, the offset of its first '='.
+    // any data after it means the padding was misplaced
+    padded_at: Option<usize>,
+
+    out: Vec<u8>,
+    out_pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> Base64Decoder<R> {
+    pub fn new(inner: R) -> Base64Decoder<R> {
+        return Base64Decoder::with_config(inner, Base64Config::STANDARD);
+    }
+
+    pub fn with_config(inner: R, config: Base64Config) -> Base64Decoder<R> {
+        return Base64Decoder {
+            inner, config,
+            quantum: Vec::with_capacity(4), quantum_offsets: Vec::with_capacity(4),
+            offset: 0, chars: 0,
+            padded_at: None,
+            out: Vec::new(), out_pos: 0, eof: false,
+        };
+    }
+
+    pub fn into_inner(self) -> R {
+        return self.inner;
+    }
+
+    // decode characters that have already been stripped of whitespace,
+    // moving error offsets back to where the characters were in the stream
+    fn decode_data(&mut self, data: &[u8], offsets: &[usize]) -> Result<(), EncodingError> {
+        let unwrapped = Base64Config { line_wrap: None, ..self.config };
+
+        // data is all ASCII, so it is valid UTF-8
+        let decoded = base64_decode_with(std::str::from_utf8(data).unwrap(), &unwrapped)
+            .map_err(|e| match e.offset() {
+                Some(i) => e.at(offsets[i]),
+                None => e,
+            })?;
+        self.out.extend_from_slice(&decoded);
+
+        let last_quantum = &data[data.len().saturating_sub(4)..];
+        if let Some(i) = last_quantum.iter().position(|&c| c == b'=') {
+            self.padded_at = Some(offsets[data.len() - last_quantum.len() + i]);
+        }
+
+        return Ok(());
+    }
+
+    fn decode_chunk(&mut self, chunk: &[u8]) -> Result<(), EncodingError> {
+        let mut data = std::mem::take(&mut self.quantum);
+        let mut offsets = std::mem::take(&mut self.quantum_offsets);
+
+        for &c in chunk {
+            let offset = self.offset;
+            self.offset += 1;
+
+            if self.config.skips_whitespace() && c.is_ascii_whitespace() {
+                continue;
+            }
+
+            if let Some(padding) = self.padded_at {
+                return Err(EncodingError::InvalidPadding { offset: padding });
+            }
+
+            if !c.is_ascii() {
+                return Err(EncodingError::InvalidDigit { byte: c, offset });
+            }
+
+            data.push(c);
+            offsets.push(offset);
+            self.chars += 1;
+        }
+
+        // keep the incomplete quantum at the end for the next chunk
+        let full = data.len() / 4 * 4;
+        self.quantum.extend_from_slice(&data[full..]);
+        self.quantum_offsets.extend_from_slice(&offsets[full..]);
+
+        if full > 0 {
+            self.decode_data(&data[..full], &offsets[..full])?;
+
+            // padding in the middle of a chunk is caught by decode_data,
+            // but padding followed only by a partial quantum is not
+            if let (Some(padding), false) = (self.padded_at, self.quantum.is_empty()) {
+                return Err(EncodingError::InvalidPadding { offset: padding });
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn decode_end(&mut self) -> Result<(), EncodingError> {
+        if self.quantum.is_empty() {
+            return Ok(());
+        }
+
+        if self.config.requires_padding() || self.quantum.len() == 1 {
+            return Err(EncodingError::InvalidLength { len: self.chars, multiple: 4 });
+        }
+
+        let data = std::mem::take(&mut self.quantum);
+        let offsets = std::mem::take(&mut self.quantum_offsets);
+        return self.decode_data(&data, &offsets);
+    }
+}
+
+impl<R: Read> Read for Base64Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos == self.out.len() && !self.eof {
+            self.out.clear();
+            self.out_pos = 0;
+
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.eof = true;
+                self.decode_end().map_err(invalid_data)?;
+            } else {
+                self.decode_chunk(&chunk[..n]).map_err(invalid_data)?;
+            }
+        }
+
+        let n = cmp::min(buf.len(), self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos .. self.out_pos+n]);
+        self.out_pos += n;
+        return Ok(n);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    // hands out its data at most `chunk` bytes per read, to exercise quantum carry-over
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl<'a> Read for ChunkedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = cmp::min(cmp::min(self.chunk, buf.len()), self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            return Ok(n);
+        }
+    }
+
+    fn decode_all<R: Read>(mut decoder: R) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out)?;
+        return Ok(out);
+    }
+
+    fn stream_error(err: io::Error) -> EncodingError {
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        return *err.into_inner().unwrap().downcast::<EncodingError>().unwrap();
+    }
+
+    #[test]
+    fn test_hex_stream() {
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000];
+        rng.fill(&mut bytes[..]);
+
+        for &chunk in [1, 2, 3, 7, 64, 1000].iter() {
+            let mut encoder = HexEncoder::with_config(Vec::new(), HexConfig::UPPER);
+            for piece in bytes.chunks(chunk) {
+                encoder.write_all(piece).unwrap();
+            }
+            let hex = encoder.into_inner();
+            assert_eq!(hex, hex_encode_with(&bytes, &HexConfig::UPPER).as_bytes());
+
+            let reader = ChunkedReader { data: &hex, chunk };
+            assert_eq!(decode_all(HexDecoder::new(reader)).unwrap(), bytes);
+        }
+
+        // "0x" prefixes are recognized even when split between reads
+        let dump = b"0xde 0xad\n0XBE:ef";
+        let reader = ChunkedReader { data: dump, chunk: 1 };
+        let decoded = decode_all(HexDecoder::with_config(reader, HexConfig::LENIENT)).unwrap();
+        assert_eq!(decoded, hex_decode_with("0xde 0xad\n0XBE:ef", &HexConfig::LENIENT).unwrap());
+
+        let reader = ChunkedReader { data: b"00112g", chunk: 3 };
+        let err = stream_error(decode_all(HexDecoder::new(reader)).unwrap_err());
+        assert_eq!(err, EncodingError::InvalidDigit { byte: b'g', offset: 5 });
+
+        let reader = ChunkedReader { data: b"0011 2", chunk: 3 };
+        let err = stream_error(decode_all(HexDecoder::with_config(reader, HexConfig::LENIENT)).unwrap_err());
+        assert_eq!(err, EncodingError::InvalidLength { len: 5, multiple: 2 });
+
+        let reader = ChunkedReader { data: b"0xde 0x0a 0", chunk: 2 };
+        let err = stream_error(decode_all(HexDecoder::with_config(reader, HexConfig::LENIENT)).unwrap_err());
+        assert_eq!(Err(err), hex_decode_with("0xde 0x0a 0", &HexConfig::LENIENT));
+    }
+
+    #[test]
+    fn test_base64_stream() {
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000];
+        rng.fill(&mut bytes[..]);
+
+        let configs = [Base64Config::STANDARD, Base64Config::STANDARD_NO_PAD,
+                       Base64Config::URL_SAFE_NO_PAD, Base64Config::MIME,
+                       Base64Config::STRICT, Base64Config::LENIENT];
+        for config in configs.iter() {
+            for &len in [0, 1, 2, 3, 58, 59, 60, 1000].iter() {
+                for &chunk in [1, 2, 5, 76, 1000].iter() {
+                    let expected = base64_encode_with(&bytes[..len], config);
+
+                    let mut encoder = Base64Encoder::with_config(Vec::new(), *config);
+                    for piece in bytes[..len].chunks(chunk) {
+                        encoder.write_all(piece).unwrap();
+                    }
+                    let encoded = encoder.finish().unwrap();
+                    assert_eq!(encoded, expected.as_bytes());
+
+                    let reader = ChunkedReader { data: &encoded, chunk };
+                    let decoded = decode_all(Base64Decoder::with_config(reader, *config)).unwrap();
+                    assert_eq!(decoded, &bytes[..len]);
+                }
+            }
+        }
+
+        // dropping the encoder still writes out the final quantum
+        let mut out = Vec::new();
+        {
+            let mut encoder = Base64Encoder::new(&mut out);
+            encoder.write_all(b"fooba").unwrap();
+        }
+        assert_eq!(out, b"Zm9vYmE=");
+
+        // 6.txt is wrapped at 60 columns, but MIME decoding does not care about the width
+        let file = std::fs::File::open("6.txt").unwrap();
+        let decoded = decode_all(Base64Decoder::with_config(file, Base64Config::MIME)).unwrap();
+        let expected = base64_decode_with(&std::fs::read_to_string("6.txt").unwrap(), &Base64Config::MIME).unwrap();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_base64_stream_errors() {
+        let decode = |data: &[u8], chunk: usize, config: Base64Config| {
+            let reader = ChunkedReader { data, chunk };
+            return decode_all(Base64Decoder::with_config(reader, config)).map_err(stream_error);
+        };
+
+        for &chunk in [1, 3, 4, 100].iter() {
+            assert_eq!(decode(b"QUJDQ*==", chunk, Base64Config::STANDARD), Err(EncodingError::InvalidDigit { byte: b'*', offset: 5 }));
+            assert_eq!(decode(b"QQ==QUJD", chunk, Base64Config::STANDARD), Err(EncodingError::InvalidPadding { offset: 2 }));
+            assert_eq!(decode(b"QUI=Q", chunk, Base64Config::STANDARD), Err(EncodingError::InvalidPadding { offset: 3 }));
+            assert_eq!(decode(b"QUJDQU", chunk, Base64Config::STANDARD), Err(EncodingError::InvalidLength { len: 6, multiple: 4 }));
+            assert_eq!(decode(b"QUJDQ", chunk, Base64Config::STANDARD_NO_PAD), Err(EncodingError::InvalidLength { len: 5, multiple: 4 }));
+            assert_eq!(decode(b"QU\r\nJ*", chunk, Base64Config::MIME), Err(EncodingError::InvalidDigit { byte: b'*', offset: 5 }));
+            assert_eq!(decode(b"QQ==\r\n", chunk, Base64Config::MIME), Ok(b"A".to_vec()));
+        }
+    }
+}
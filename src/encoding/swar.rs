@@ -0,0 +1,556 @@
+use std::convert::TryInto;
+use crate::encoding::{self, EncodingError, HexConfig, Base64Alphabet, Base64Config};
+use crate::encoding::base64_char_with;
+
+// the routines in encoding::simd on stable rust: "SIMD within a register", treating a
+// u128 as 16 byte lanes. plain integer arithmetic carries and borrows across lanes,
+// so every lane operation below is arranged so that it never does
+
+const WORD: usize = 16;
+
+const LANES_01: u128 = u128::MAX / 0xff; // 0x0101..01
+const LANES_0F: u128 = LANES_01 * 0x0f;
+const LANES_80: u128 = LANES_01 * 0x80;
+
+// low bits of every 16 and 32 bit lane, for packing and unpacking sextets and nibbles
+const PAIRS_01: u128 = u128::MAX / 0xffff; // 0x0001..0001
+const QUADS_01: u128 = u128::MAX / 0xffff_ffff; // 0x00000001..00000001
+const OCTS_01: u128 = u128::MAX / (u64::MAX as u128);
+
+// x with every lane set to b
+fn splat(b: u8) -> u128 {
+    return LANES_01 * b as u128;
+}
+
+// 0x80 in the lanes of x that are >= lo. lanes of x must be ASCII
+fn lanes_ge(x: u128, lo: u8) -> u128 {
+    return ((x | LANES_80) - splat(lo)) & LANES_80;
+}
+
+// 0x80 in the lanes of x that are <= hi. lanes of x must be ASCII
+fn lanes_le(x: u128, hi: u8) -> u128 {
+    return ((splat(hi) | LANES_80) - x) & LANES_80;
+}
+
+fn lanes_in_range(x: u128, lo: u8, hi: u8) -> u128 {
+    return lanes_ge(x, lo) & lanes_le(x, hi);
+}
+
+// widen the 0x80 flags returned above to whole 0xff lanes
+fn lanes_mask(flags: u128) -> u128 {
+    return (flags >> 7) * 0xff;
+}
+
+// lane-wise wrapping addition
+fn lanes_add(x: u128, y: u128) -> u128 {
+    return ((x & !LANES_80) + (y & !LANES_80)) ^ ((x ^ y) & LANES_80);
+}
+
+// lanes of mask are 0x00 or 0xff; take x where it is set and y elsewhere
+fn lanes_select(mask: u128, x: u128, y: u128) -> u128 {
+    return (x & mask) | (y & !mask);
+}
+
+fn load(bytes: &[u8]) -> u128 {
+    return u128::from_le_bytes(bytes.try_into().unwrap());
+}
+
+// XOR two byte vectors
+pub fn xor_bytes(buf1: &[u8], buf2: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    let mut out: Vec<u8> = buf1.to_vec();
+    xor_assign(&mut out, buf2)?;
+    return Ok(out);
+}
+
+// XOR src into dst in place, a u128 at a time
+pub fn xor_assign(dst: &mut [u8], src: &[u8]) -> Result<(), EncodingError> {
+    if dst.len() != src.len() {
+        return Err(EncodingError::LengthMismatch { left: dst.len(), right: src.len() });
+    }
+
+    let mut dst_chunks = dst.chunks_exact_mut(WORD);
+    let mut src_chunks = src.chunks_exact(WORD);
+    for (dst_chunk, src_chunk) in (&mut dst_chunks).zip(&mut src_chunks) {
+        let chunk_res = load(dst_chunk) ^ load(src_chunk);
+        dst_chunk.copy_from_slice(&chunk_res.to_le_bytes());
+    }
+
+    for (d, s) in dst_chunks.into_remainder().iter_mut().zip(src_chunks.remainder()) {
+        *d ^= s;
+    }
+
+    return Ok(());
+}
+
+// write buf1 XOR buf2 into out, without allocating. all three must be the same length
+pub fn xor_into(out: &mut [u8], buf1: &[u8], buf2: &[u8]) -> Result<(), EncodingError> {
+    if buf1.len() != buf2.len() {
+        return Err(EncodingError::LengthMismatch { left: buf1.len(), right: buf2.len() });
+    }
+    if out.len() != buf1.len() {
+        return Err(EncodingError::LengthMismatch { left: out.len(), right: buf1.len() });
+    }
+
+    let mut out_chunks = out.chunks_exact_mut(WORD);
+    let mut chunks1 = buf1.chunks_exact(WORD);
+    let mut chunks2 = buf2.chunks_exact(WORD);
+    for ((out_chunk, chunk1), chunk2) in (&mut out_chunks).zip(&mut chunks1).zip(&mut chunks2) {
+        let chunk_res = load(chunk1) ^ load(chunk2);
+        out_chunk.copy_from_slice(&chunk_res.to_le_bytes());
+    }
+
+    let remainders = chunks1.remainder().iter().zip(chunks2.remainder());
+    for (o, (b1, b2)) in out_chunks.into_remainder().iter_mut().zip(remainders) {
+        *o = b1 ^ b2;
+    }
+
+    return Ok(());
+}
+
+// see simd::XOR_KEY_BLOCK_VECTORS
+const XOR_KEY_BLOCK_WORDS: usize = 16;
+
+// XOR bytes with key repeated end to end
+pub fn xor_with_key(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    if key.is_empty() {
+        return Err(EncodingError::EmptyKey);
+    }
+
+    let copies = (XOR_KEY_BLOCK_WORDS * WORD).div_ceil(key.len());
+    let key_block: Vec<u8> = key.repeat(copies);
+
+    let mut result: Vec<u8> = bytes.to_vec();
+    for chunk in result.chunks_mut(key_block.len()) {
+        let n = chunk.len();
+        xor_assign(chunk, &key_block[..n])?;
+    }
+
+    return Ok(result);
+}
+
+// XOR bytes in place with a keystream, a u128 of keystream at a time.
+// if the keystream ends early, the bytes past its end are left as they are
+pub fn xor_with_keystream(bytes: &mut [u8], mut keystream: impl Iterator<Item=u8>) {
+    let mut key_buf = [0u8; WORD];
+    for chunk in bytes.chunks_mut(WORD) {
+        let mut n = 0;
+        for (k, b) in key_buf[..chunk.len()].iter_mut().zip(&mut keystream) {
+            *k = b;
+            n += 1;
+        }
+
+        if n == WORD {
+            let chunk_res = load(chunk) ^ u128::from_le_bytes(key_buf);
+            chunk.copy_from_slice(&chunk_res.to_le_bytes());
+
+        } else {
+            for (b, k) in chunk.iter_mut().zip(&key_buf[..n]) {
+                *b ^= k;
+            }
+        }
+
+        if n < chunk.len() {
+            return;
+        }
+    }
+}
+
+// move byte k of x to lane 2k, leaving the odd lanes zero
+fn spread_bytes(x: u64) -> u128 {
+    let mut x = x as u128;
+    x = (x | (x << 32)) & (OCTS_01 * 0xffff_ffff);
+    x = (x | (x << 16)) & (QUADS_01 * 0xffff);
+    x = (x | (x << 8)) & (PAIRS_01 * 0xff);
+    return x;
+}
+
+// decode a hex string into raw bytes, 16 characters per iteration
+pub fn hex_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    if !str.len().is_multiple_of(2) {
+        return Err(EncodingError::InvalidLength { len: str.len(), multiple: 2 });
+    }
+
+    let str_bytes = str.as_bytes();
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/2);
+
+    let mut i: usize = 0;
+    while i + WORD <= str_bytes.len() {
+        let chars = load(&str_bytes[i..i+WORD]);
+
+        // the range checks need ASCII lanes. on anything invalid, stop and let the
+        // scalar decoder find and report the error in the rest of the input
+        if chars & LANES_80 != 0 {
+            break;
+        }
+        let digit = lanes_in_range(chars, b'0', b'9');
+        let letter = lanes_in_range(chars | splat(0x20), b'a', b'f');
+        if digit | letter != LANES_80 {
+            break;
+        }
+
+        // '0'..'9' and 'a'..'f' / 'A'..'F' end in the nibbles 0..9 and 1..6
+        let vals = (chars & LANES_0F) + (lanes_mask(letter) & splat(9));
+
+        // each 16 bit lane becomes one byte, then the bytes are squeezed together
+        let mut block = ((vals & (PAIRS_01 * 0xff)) << 4) | ((vals >> 8) & (PAIRS_01 * 0xff));
+        block = (block | (block >> 8)) & (QUADS_01 * 0xffff);
+        block = (block | (block >> 16)) & (OCTS_01 * 0xffff_ffff);
+        block = (block | (block >> 32)) & (u64::MAX as u128);
+        bytes.extend_from_slice(&(block as u64).to_le_bytes());
+        i += WORD;
+    }
+
+    // everything before i was validated as ASCII, so i is a char boundary
+    let tail = encoding::hex_decode(&str[i..]).map_err(|e| e.at(i + e.offset().unwrap()))?;
+    bytes.extend_from_slice(&tail);
+
+    return Ok(bytes);
+}
+
+// decode hex, optionally skipping the separators allowed by config.
+// only strict input goes word at a time; separators go through the scalar decoder
+pub fn hex_decode_with(str: &str, config: &HexConfig) -> Result<Vec<u8>, EncodingError> {
+    if config.skip_separators {
+        return encoding::hex_decode_with(str, config);
+    }
+
+    return hex_decode(str);
+}
+
+// encode raw bytes as a lowercase hex string
+pub fn hex_encode(bytes: &[u8]) -> String {
+    return hex_encode_with(bytes, &HexConfig::LOWER);
+}
+
+// encode raw bytes as hex in the digit case chosen by config, 8 bytes per iteration
+pub fn hex_encode_with(bytes: &[u8], config: &HexConfig) -> String {
+    let mut hex_bytes: Vec<u8> = Vec::with_capacity(bytes.len()*2);
+    let letter_offset = if config.uppercase { b'A' - b'0' - 10 } else { b'a' - b'0' - 10 };
+
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let block = u64::from_le_bytes(chunk.try_into().unwrap());
+        let hi = (block >> 4) & (u64::MAX / 0xff * 0x0f);
+        let lo = block & (u64::MAX / 0xff * 0x0f);
+        let nibbles = spread_bytes(hi) | (spread_bytes(lo) << 8);
+
+        // every lane stays below 0x80, so plain addition cannot carry between lanes
+        let letters = lanes_mask(lanes_ge(nibbles, 10));
+        let chars = nibbles + splat(b'0') + (letters & splat(letter_offset));
+        hex_bytes.extend_from_slice(&chars.to_le_bytes());
+    }
+
+    hex_bytes.extend_from_slice(encoding::hex_encode_with(chunks.remainder(), config).as_bytes());
+
+    // every character is a hex digit, so this is ASCII
+    return unsafe { String::from_utf8_unchecked(hex_bytes) };
+}
+
+// decode hex into a string with one char per byte.
+// bytes >= 0x80 become the chars U+0080..U+00FF, so use hex_decode for binary data
+pub fn hex_to_ascii_str(str: &str) -> Result<String, EncodingError> {
+    return Ok(hex_decode(str)?.into_iter().map(|b| b as char).collect());
+}
+
+pub fn ascii_to_hex_str(str: &str) -> String {
+    return hex_encode(str.as_bytes());
+}
+
+// re-encode a hex string as padded base64
+pub fn hex_to_base64(str: &str) -> Result<String, EncodingError> {
+    return Ok(bytes_to_base64(&hex_decode(str)?));
+}
+
+// map 16 sextets to base64 characters at once
+fn base64_chars_swar(sextets: u128, alphabet: Base64Alphabet) -> u128 {
+    let char62 = base64_char_with(62, alphabet).unwrap() as u8;
+    let char63 = base64_char_with(63, alphabet).unwrap() as u8;
+
+    // add the distance from the first sextet of each range to its first character
+    let mut offset = splat(65); // A-Z
+    offset = lanes_select(lanes_mask(lanes_ge(sextets, 26)), splat(71), offset); // a-z
+    offset = lanes_select(lanes_mask(lanes_ge(sextets, 52)), splat(4u8.wrapping_neg()), offset); // 0-9
+    offset = lanes_select(lanes_mask(lanes_in_range(sextets, 62, 62)), splat(char62.wrapping_sub(62)), offset);
+    offset = lanes_select(lanes_mask(lanes_ge(sextets, 63)), splat(char63.wrapping_sub(63)), offset);
+    return lanes_add(sextets, offset);
+}
+
+// map 16 base64 characters back to sextets, or None if any of them is
+// outside the alphabet, including '='
+fn base64_vals_swar(chars: u128, alphabet: Base64Alphabet) -> Option<u128> {
+    if chars & LANES_80 != 0 {
+        return None;
+    }
+
+    let char62 = base64_char_with(62, alphabet).unwrap() as u8;
+    let char63 = base64_char_with(63, alphabet).unwrap() as u8;
+
+    let upper = lanes_in_range(chars, b'A', b'Z');
+    let lower = lanes_in_range(chars, b'a', b'z');
+    let digit = lanes_in_range(chars, b'0', b'9');
+    let is62 = lanes_in_range(chars, char62, char62);
+    let is63 = lanes_in_range(chars, char63, char63);
+    if upper | lower | digit | is62 | is63 != LANES_80 {
+        return None;
+    }
+
+    // the ranges are disjoint, so the offsets can simply be or'ed together
+    let offset = (lanes_mask(upper) & splat(65u8.wrapping_neg()))
+        | (lanes_mask(lower) & splat(71u8.wrapping_neg()))
+        | (lanes_mask(digit) & splat(4))
+        | (lanes_mask(is62) & splat(62u8.wrapping_sub(char62)))
+        | (lanes_mask(is63) & splat(63u8.wrapping_sub(char63)));
+    return Some(lanes_add(chars, offset));
+}
+
+// padded base64 in the given alphabet, 12 input bytes per iteration
+fn base64_encode_alphabet(bytes: &[u8], alphabet: Base64Alphabet) -> String {
+    let mut b64_bytes: Vec<u8> = Vec::with_capacity(bytes.len().div_ceil(3)*4);
+
+    let mut chunks = bytes.chunks_exact(12);
+    for chunk in &mut chunks {
+        // one 24 bit group per 32 bit lane
+        let mut groups: u128 = 0;
+        for (k, group) in chunk.chunks_exact(3).enumerate() {
+            let group = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+            groups |= (group as u128) << (32*k);
+        }
+
+        // split each group into two 12 bit halves, then each half into two sextets
+        let halves = ((groups >> 12) & (QUADS_01 * 0xfff)) | ((groups & (QUADS_01 * 0xfff)) << 16);
+        let sextets = ((halves >> 6) & (PAIRS_01 * 0x3f)) | ((halves & (PAIRS_01 * 0x3f)) << 8);
+
+        b64_bytes.extend_from_slice(&base64_chars_swar(sextets, alphabet).to_le_bytes());
+    }
+
+    let scalar_config = Base64Config { alphabet, ..Base64Config::STANDARD };
+    b64_bytes.extend_from_slice(encoding::base64_encode_with(chunks.remainder(), &scalar_config).as_bytes());
+
+    // every character came from the base64 alphabet, so this is ASCII
+    return unsafe { String::from_utf8_unchecked(b64_bytes) };
+}
+
+// decode base64 without whitespace, 16 characters per iteration
+fn base64_decode_unwrapped(str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+    let str_bytes = str.as_bytes();
+
+    // unpadded input may end in a 2 or 3 character quantum, but never a single character
+    let len_ok =
+        if config.requires_padding() {
+            str_bytes.len().is_multiple_of(4)
+        } else {
+            str_bytes.len() % 4 != 1
+        };
+    if !len_ok {
+        return Err(EncodingError::InvalidLength { len: str_bytes.len(), multiple: 4 });
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(str_bytes.len()/4*3 + 2);
+
+    // the final quantum may hold padding, so it is always left to the scalar decoder.
+    // so is everything from the first block with a character outside the alphabet:
+    // every quantum before it was valid, so the scalar decoder reports the same error
+    let mut i: usize = 0;
+    while i + WORD + 4 <= str_bytes.len() {
+        let vals = match base64_vals_swar(load(&str_bytes[i..i+WORD]), config.alphabet) {
+            Some(vals) => vals,
+            None => break,
+        };
+
+        // pairs of sextets become 12 bit halves, pairs of halves become 24 bit groups
+        let halves = ((vals & (PAIRS_01 * 0xff)) << 6) | ((vals >> 8) & (PAIRS_01 * 0xff));
+        let groups = ((halves & (QUADS_01 * 0xffff)) << 12) | ((halves >> 16) & (QUADS_01 * 0xffff));
+        for k in 0..4 {
+            bytes.extend_from_slice(&((groups >> (32*k)) as u32).to_be_bytes()[1..]);
+        }
+        i += WORD;
+    }
+
+    // everything before i was validated as ASCII, so i is a char boundary
+    let tail = encoding::base64_decode_with(&str[i..], config).map_err(|e| match e.offset() {
+        Some(offset) => e.at(i + offset),
+        None => e,
+    })?;
+    bytes.extend_from_slice(&tail);
+
+    return Ok(bytes);
+}
+
+// decode a padded base64 string into raw bytes
+pub fn base64_decode(str: &str) -> Result<Vec<u8>, EncodingError> {
+    return base64_decode_with(str, &Base64Config::STANDARD);
+}
+
+// decode base64 in the variant described by config
+pub fn base64_decode_with(str: &str, config: &Base64Config) -> Result<Vec<u8>, EncodingError> {
+    if !config.skips_whitespace() {
+        return base64_decode_unwrapped(str, config);
+    }
+
+    // squeeze out whitespace, then move error offsets back to the original input.
+    // whitespace is never part of a multi-byte character, so data stays valid UTF-8
+    let str_bytes = str.as_bytes();
+    let positions: Vec<usize> = (0..str_bytes.len()).filter(|&i| !str_bytes[i].is_ascii_whitespace()).collect();
+    let data = String::from_utf8(positions.iter().map(|&i| str_bytes[i]).collect()).unwrap();
+
+    let unwrapped = Base64Config { line_wrap: None, ..*config };
+    return base64_decode_unwrapped(&data, &unwrapped).map_err(|e| match e {
+        EncodingError::InvalidLength { .. } => e,
+        _ => e.at(positions[e.offset().unwrap()]),
+    });
+}
+
+// encode raw bytes as padded base64
+pub fn bytes_to_base64(bytes: &[u8]) -> String {
+    return base64_encode_alphabet(bytes, Base64Alphabet::Standard);
+}
+
+// encode raw bytes as base64; the byte-oriented twin of base64_decode
+pub fn base64_encode(bytes: &[u8]) -> String {
+    return bytes_to_base64(bytes);
+}
+
+// encode raw bytes as base64 in the variant described by config
+pub fn base64_encode_with(bytes: &[u8], config: &Base64Config) -> String {
+    let mut b64_str = base64_encode_alphabet(bytes, config.alphabet);
+
+    if !config.pad {
+        b64_str.truncate(b64_str.trim_end_matches('=').len());
+    }
+
+    return match config.line_wrap {
+        Some(width) if width > 0 => {
+            let lines: Vec<&str> = b64_str.as_bytes()
+                .chunks(width)
+                .map(|line| std::str::from_utf8(line).unwrap())
+                .collect();
+            lines.join("\r\n")
+        },
+
+        _ => b64_str,
+    };
+}
+
+// decode base64 into a string with one char per byte; see hex_to_ascii_str
+pub fn base64_to_ascii_str(str: &str) -> Result<String, EncodingError> {
+    return Ok(base64_decode(str)?.into_iter().map(|b| b as char).collect());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_xor_matches_scalar() {
+        let mut rng = rand::thread_rng();
+        let mut bytes1 = vec![0u8; 300];
+        let mut bytes2 = vec![0u8; 300];
+        rng.fill(&mut bytes1[..]);
+        rng.fill(&mut bytes2[..]);
+
+        for start in 0..WORD {
+            for len in [0, 1, WORD-1, WORD, WORD+1, 3*WORD+5, 300-start] {
+                let (a, b) = (&bytes1[start..start+len], &bytes2[start..start+len]);
+                let expected = encoding::xor_bytes(a, b).unwrap();
+                assert_eq!(xor_bytes(a, b).unwrap(), expected);
+
+                let mut out = vec![0u8; len];
+                xor_into(&mut out, a, b).unwrap();
+                assert_eq!(out, expected);
+            }
+        }
+
+        for key_len in [1, 3, WORD, 29, 300] {
+            let key = &bytes2[..key_len];
+            let expected = encoding::xor_with_key(&bytes1, key).unwrap();
+            assert_eq!(xor_with_key(&bytes1, key).unwrap(), expected);
+
+            let mut streamed = bytes1.clone();
+            encoding::xor_with_keystream(&mut streamed, key.iter().cloned());
+            let mut streamed_swar = bytes1.clone();
+            xor_with_keystream(&mut streamed_swar, key.iter().cloned());
+            assert_eq!(streamed_swar, streamed);
+        }
+
+        let mut out = [0u8; 3];
+        assert_eq!(xor_assign(&mut out, &bytes1[..4]), Err(EncodingError::LengthMismatch { left: 3, right: 4 }));
+        assert_eq!(xor_with_key(b"abc", b""), Err(EncodingError::EmptyKey));
+    }
+
+    #[test]
+    fn test_hex_matches_scalar() {
+        let mut rng = rand::thread_rng();
+
+        for len in 0..100 {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+
+            for config in [HexConfig::LOWER, HexConfig::UPPER].iter() {
+                let encoded = encoding::hex_encode_with(&bytes, config);
+                assert_eq!(hex_encode_with(&bytes, config), encoded);
+                assert_eq!(hex_decode(&encoded).unwrap(), bytes);
+
+                // corrupt one character and expect the same error as the scalar decoder
+                if !encoded.is_empty() {
+                    let mut corrupt = encoded.clone().into_bytes();
+                    let pos = rng.gen_range(0..corrupt.len());
+                    corrupt[pos] = b"gG/:@`\n "[rng.gen_range(0..8)];
+                    let corrupt = String::from_utf8(corrupt).unwrap();
+                    assert_eq!(hex_decode(&corrupt), encoding::hex_decode(&corrupt), "{:?}", corrupt);
+                }
+            }
+        }
+
+        let non_ascii = "00112233445566778899aabbccddeeff\u{e9}";
+        assert_eq!(hex_decode(non_ascii), encoding::hex_decode(non_ascii));
+    }
+
+    #[test]
+    fn test_base64_matches_scalar() {
+        let mut rng = rand::thread_rng();
+        let configs = [Base64Config::STANDARD, Base64Config::STANDARD_NO_PAD,
+                       Base64Config::URL_SAFE, Base64Config::URL_SAFE_NO_PAD,
+                       Base64Config::MIME, Base64Config::STRICT, Base64Config::LENIENT];
+
+        for len in 0..100 {
+            let mut bytes = vec![0u8; len];
+            rng.fill(&mut bytes[..]);
+
+            for config in configs.iter() {
+                let encoded = encoding::base64_encode_with(&bytes, config);
+                assert_eq!(base64_encode_with(&bytes, config), encoded);
+                assert_eq!(base64_decode_with(&encoded, config).unwrap(), bytes);
+
+                // corrupt one character and expect the same error as the scalar decoder
+                if !encoded.is_empty() {
+                    let mut corrupt = encoded.clone().into_bytes();
+                    let pos = rng.gen_range(0..corrupt.len());
+                    corrupt[pos] = b"=*\n-/\0"[rng.gen_range(0..6)];
+                    let corrupt = String::from_utf8(corrupt).unwrap();
+                    assert_eq!(base64_decode_with(&corrupt, config),
+                               encoding::base64_decode_with(&corrupt, config),
+                               "{:?} {:?}", corrupt, config);
+                }
+            }
+        }
+
+        let non_ascii = "QUJDQUJDQUJDQUJDQUJD\u{e9}UJD";
+        assert_eq!(base64_decode(non_ascii), encoding::base64_decode(non_ascii));
+    }
+
+    // compare with encoding::test::test_hex_speed and test_base64_speed
+    #[test]
+    fn test_codec_speed() {
+        time_test!();
+
+        let mut rng = rand::thread_rng();
+        let mut bytes = vec![0u8; 1000000];
+        rng.fill(&mut bytes[..]);
+
+        for _ in 0..20 {
+            assert_eq!(hex_decode(&hex_encode(&bytes)).unwrap().len(), bytes.len());
+            assert_eq!(base64_decode(&base64_encode(&bytes)).unwrap().len(), bytes.len());
+        }
+    }
+}
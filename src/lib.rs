@@ -0,0 +1,12 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+#![allow(clippy::needless_return, clippy::module_inception)]
+#[cfg(test)] #[macro_use] extern crate time_test;
+
+pub mod encoding;
+pub mod xor;
+pub mod analysis;
+pub mod ciphers;
+pub mod attacks;
+
+mod tests;
+mod properties;
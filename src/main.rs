@@ -1,10 +1,19 @@
-#![feature(portable_simd)]
-#[macro_use] extern crate time_test;
-
-mod tests;
-mod encoding;
-mod encoding_simd;
+use cryptopals::analysis::ngram::NgramScorer;
+use cryptopals::attacks;
+use cryptopals::encoding::ByteBuf;
+use std::env;
 
+// cryptopals <hex>: print the most likely single-byte XOR key and plaintext for a ciphertext
 fn main() {
-    println!("This program does nothing! Run the test suite (`cargo test`) to execute the challenges.");
+    let hex = match env::args().nth(1) {
+        Some(hex) => hex,
+        None => {
+            println!("This program does nothing! Run the test suite (`cargo test`) to execute the challenges.");
+            return;
+        }
+    };
+
+    let ciphertext: ByteBuf = hex.parse().expect("ciphertext is not valid hex");
+    let (key, _) = attacks::break_single_byte_xor(&ciphertext, &NgramScorer::english(4).unwrap());
+    println!("key {:#04x}: {}", key, String::from_utf8_lossy(&(&ciphertext ^ key)));
 }
@@ -0,0 +1,368 @@
+#[cfg(test)]
+mod properties {
+    use std::env;
+    use std::io::{Read, Write};
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use crate::encoding::{self, EncodingError, HexConfig, Base64Config, ByteBuf};
+    use crate::encoding::swar;
+    #[cfg(feature = "simd")]
+    use crate::encoding::simd;
+    use crate::encoding::stream::{HexEncoder, HexDecoder, Base64Encoder, Base64Decoder};
+    use crate::encoding::base32::{self, Base32Config};
+    use crate::encoding::base58;
+    use crate::encoding::ascii85;
+    use crate::encoding::ct;
+    use crate::encoding::pem;
+
+    // random cases per property, and the largest input they use
+    const CASES: usize = 128;
+    const MAX_LEN: usize = 4096;
+
+    // set to a number to run the suite with a different seed.
+    // a failing case prints the seed it ran with, so it can be rerun exactly
+    const SEED_ENV_VAR: &str = "CRYPTOPALS_SEED";
+    const DEFAULT_SEED: u64 = 20200118;
+
+    const HEX_CONFIGS: [HexConfig; 3] = [HexConfig::LOWER, HexConfig::UPPER, HexConfig::LENIENT];
+    const BASE64_CONFIGS: [Base64Config; 7] = [
+        Base64Config::STANDARD, Base64Config::STANDARD_NO_PAD,
+        Base64Config::URL_SAFE, Base64Config::URL_SAFE_NO_PAD,
+        Base64Config::MIME, Base64Config::STRICT, Base64Config::LENIENT,
+    ];
+
+    // characters that corrupt hex or base64 in interesting ways: out of the alphabet,
+    // in the other base64 alphabet, padding, separators and whitespace
+    const HEX_CORRUPTIONS: &[u8] = b"gG/:@`x\n 0fF";
+    const BASE64_CORRUPTIONS: &[u8] = b"=*-_+/.\n\r Aa0";
+
+    // generator of random test inputs. every property gets its own, seeded from the
+    // suite seed and the property's name, so adding a property does not change the
+    // inputs of the others
+    struct Gen {
+        rng: StdRng,
+        seed: u64,
+        property: &'static str,
+    }
+
+    impl Gen {
+        fn new(property: &'static str) -> Gen {
+            let seed = match env::var(SEED_ENV_VAR) {
+                Ok(seed) => seed.parse().unwrap_or_else(|_| panic!("{}: not a number: {:?}", SEED_ENV_VAR, seed)),
+                Err(_) => DEFAULT_SEED,
+            };
+
+            // FNV-1a, so that the per-property seeds do not depend on std's hasher
+            let mut hash: u64 = 0xcbf29ce484222325;
+            for b in property.bytes() {
+                hash = (hash ^ b as u64).wrapping_mul(0x100000001b3);
+            }
+
+            return Gen { rng: StdRng::seed_from_u64(seed ^ hash), seed, property };
+        }
+
+        // what to print when a case fails
+        fn context(&self, case: usize) -> String {
+            return format!("{} case {} ({}={})", self.property, case, SEED_ENV_VAR, self.seed);
+        }
+
+        // a length up to max. a quarter of the time it is a short one,
+        // since the edge cases (empty input, partial blocks) live there
+        fn len(&mut self, max: usize) -> usize {
+            if self.rng.gen_range(0..4) == 0 {
+                return self.rng.gen_range(0..=max.min(16));
+            }
+            return self.rng.gen_range(0..=max);
+        }
+
+        // bytes of every value, with some runs of a single byte mixed in
+        // (all-zero blocks are special in Ascii85 and Base58)
+        fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+            let len = self.len(max_len);
+            let mut bytes = vec![0u8; len];
+            match self.rng.gen_range(0..8) {
+                0 => {
+                    let b: u8 = if self.rng.gen() { 0 } else { 255 };
+                    bytes.iter_mut().for_each(|x| *x = b);
+                },
+                1 => {
+                    self.rng.fill(&mut bytes[..]);
+                    let start = self.rng.gen_range(0..=len);
+                    let end = self.rng.gen_range(start..=len);
+                    bytes[start..end].iter_mut().for_each(|x| *x = 0);
+                },
+                _ => self.rng.fill(&mut bytes[..]),
+            }
+            return bytes;
+        }
+
+        // replace one character of str with one of corruptions
+        fn corrupt(&mut self, str: &str, corruptions: &[u8]) -> String {
+            if str.is_empty() {
+                return String::from_utf8(vec![corruptions[self.rng.gen_range(0..corruptions.len())]]).unwrap();
+            }
+
+            let mut corrupt = str.as_bytes().to_vec();
+            let pos = self.rng.gen_range(0..corrupt.len());
+            corrupt[pos] = corruptions[self.rng.gen_range(0..corruptions.len())];
+            return String::from_utf8(corrupt).unwrap();
+        }
+    }
+
+    #[test]
+    fn prop_hex_round_trip() {
+        let mut gen = Gen::new("prop_hex_round_trip");
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let ctx = gen.context(case);
+
+            for config in HEX_CONFIGS.iter() {
+                let hex = encoding::hex_encode_with(&bytes, config);
+                assert_eq!(hex.len(), 2 * bytes.len(), "{}", ctx);
+                assert_eq!(encoding::hex_decode_with(&hex, config).unwrap(), bytes, "{}", ctx);
+            }
+
+            // separators between bytes, as in "de:ad be\nef" or "0xde 0xad"
+            let separators = [":", " ", "\n", " 0x", "\r\n"];
+            let mut separated = String::new();
+            for &b in &bytes {
+                separated.push_str(separators[gen.rng.gen_range(0..separators.len())]);
+                separated.push_str(&encoding::hex_encode(&[b]));
+            }
+            assert_eq!(encoding::hex_decode_with(&separated, &HexConfig::LENIENT).unwrap(), bytes, "{}", ctx);
+
+            assert_eq!(encoding::hexdump_decode(&encoding::hexdump(&bytes)).unwrap(), bytes, "{}", ctx);
+            assert_eq!(ct::ct_hex_decode(&encoding::hex_encode(&bytes)).unwrap(), bytes, "{}", ctx);
+        }
+    }
+
+    #[test]
+    fn prop_base64_round_trip() {
+        let mut gen = Gen::new("prop_base64_round_trip");
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let ctx = gen.context(case);
+
+            for config in BASE64_CONFIGS.iter() {
+                let b64 = encoding::base64_encode_with(&bytes, config);
+                assert_eq!(encoding::base64_decode_with(&b64, config).unwrap(), bytes, "{} {:?}", ctx, config);
+            }
+
+            let b64 = encoding::base64_encode(&bytes);
+            assert_eq!(b64.len(), bytes.len().div_ceil(3) * 4, "{}", ctx);
+            assert_eq!(ct::ct_base64_decode(&b64).unwrap(), bytes, "{}", ctx);
+            assert_eq!(ByteBuf::from_base64(&b64).unwrap(), ByteBuf(bytes.clone()), "{}", ctx);
+
+            let pem_str = pem::encode("PROPERTY", &bytes);
+            assert_eq!(pem::decode(&pem_str).unwrap(), vec![("PROPERTY".to_string(), bytes.clone())], "{}", ctx);
+        }
+    }
+
+    // the streaming codecs, fed in random-sized pieces
+    #[test]
+    fn prop_stream_round_trip() {
+        let mut gen = Gen::new("prop_stream_round_trip");
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let ctx = gen.context(case);
+
+            let mut hex_encoder = HexEncoder::new(Vec::new());
+            let mut b64_encoder = Base64Encoder::with_config(Vec::new(), Base64Config::MIME);
+            let mut rest = &bytes[..];
+            while !rest.is_empty() {
+                let n = gen.rng.gen_range(1..=rest.len().min(100));
+                hex_encoder.write_all(&rest[..n]).unwrap();
+                b64_encoder.write_all(&rest[..n]).unwrap();
+                rest = &rest[n..];
+            }
+
+            let hex = hex_encoder.into_inner();
+            let b64 = b64_encoder.finish().unwrap();
+            assert_eq!(hex, encoding::hex_encode(&bytes).as_bytes(), "{}", ctx);
+            assert_eq!(b64, encoding::base64_encode_with(&bytes, &Base64Config::MIME).as_bytes(), "{}", ctx);
+
+            let mut decoded = Vec::new();
+            HexDecoder::new(&hex[..]).read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, bytes, "{}", ctx);
+
+            let mut decoded = Vec::new();
+            Base64Decoder::with_config(&b64[..], Base64Config::MIME).read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, bytes, "{}", ctx);
+        }
+    }
+
+    #[test]
+    fn prop_other_codecs_round_trip() {
+        let mut gen = Gen::new("prop_other_codecs_round_trip");
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let ctx = gen.context(case);
+
+            for config in [Base32Config::STANDARD, Base32Config::STANDARD_NO_PAD, Base32Config::HEX].iter() {
+                let b32 = base32::base32_encode_with(&bytes, config);
+                assert_eq!(base32::base32_decode_with(&b32, config).unwrap(), bytes, "{}", ctx);
+            }
+
+            let a85 = ascii85::ascii85_encode(&bytes);
+            assert_eq!(ascii85::ascii85_decode(&a85).unwrap(), bytes, "{}", ctx);
+
+            let whole_groups = &bytes[..bytes.len() / 4 * 4];
+            let z85 = ascii85::z85_encode(whole_groups).unwrap();
+            assert_eq!(ascii85::z85_decode(&z85).unwrap(), whole_groups, "{}", ctx);
+
+            // base58 is quadratic in the input length, so it gets shorter inputs
+            let short = &bytes[..bytes.len().min(256)];
+            let b58 = base58::base58_encode(short);
+            assert_eq!(base58::base58_decode(&b58).unwrap(), short, "{}", ctx);
+            let b58check = base58::base58check_encode(short);
+            assert_eq!(base58::base58check_decode(&b58check).unwrap(), short, "{}", ctx);
+        }
+    }
+
+    #[test]
+    fn prop_xor_inverts() {
+        let mut gen = Gen::new("prop_xor_inverts");
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let mut other = vec![0u8; bytes.len()];
+            gen.rng.fill(&mut other[..]);
+            let key = gen.bytes(64);
+            let ctx = gen.context(case);
+
+            let xored = encoding::xor_bytes(&bytes, &other).unwrap();
+            assert_eq!(encoding::xor_bytes(&xored, &other).unwrap(), bytes, "{}", ctx);
+
+            if key.is_empty() {
+                assert_eq!(encoding::xor_with_key(&bytes, &key), Err(EncodingError::EmptyKey), "{}", ctx);
+            } else {
+                let encrypted = encoding::xor_with_key(&bytes, &key).unwrap();
+                assert_eq!(encoding::xor_with_key(&encrypted, &key).unwrap(), bytes, "{}", ctx);
+            }
+        }
+    }
+
+    // encoding::swar against encoding, on valid and corrupted input
+    #[test]
+    fn prop_swar_matches_scalar() {
+        let mut gen = Gen::new("prop_swar_matches_scalar");
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let mut other = vec![0u8; bytes.len()];
+            gen.rng.fill(&mut other[..]);
+            let key = gen.bytes(64);
+            let ctx = gen.context(case);
+
+            assert_eq!(swar::xor_bytes(&bytes, &other), encoding::xor_bytes(&bytes, &other), "{}", ctx);
+            assert_eq!(swar::xor_bytes(&bytes, &key), encoding::xor_bytes(&bytes, &key), "{}", ctx);
+            assert_eq!(swar::xor_with_key(&bytes, &key), encoding::xor_with_key(&bytes, &key), "{}", ctx);
+
+            let (mut swar_out, mut scalar_out) = (bytes.clone(), bytes.clone());
+            swar::xor_with_keystream(&mut swar_out, key.iter().cloned());
+            encoding::xor_with_keystream(&mut scalar_out, key.iter().cloned());
+            assert_eq!(swar_out, scalar_out, "{}", ctx);
+
+            for config in HEX_CONFIGS.iter() {
+                let encoded = encoding::hex_encode_with(&bytes, config);
+                assert_eq!(swar::hex_encode_with(&bytes, config), encoded, "{}", ctx);
+                assert_eq!(swar::hex_decode_with(&encoded, config), encoding::hex_decode_with(&encoded, config), "{}", ctx);
+
+                let corrupt = gen.corrupt(&encoded, HEX_CORRUPTIONS);
+                assert_eq!(swar::hex_decode_with(&corrupt, config), encoding::hex_decode_with(&corrupt, config), "{} {:?}", ctx, corrupt);
+            }
+
+            for config in BASE64_CONFIGS.iter() {
+                let encoded = encoding::base64_encode_with(&bytes, config);
+                assert_eq!(swar::base64_encode_with(&bytes, config), encoded, "{} {:?}", ctx, config);
+                assert_eq!(swar::base64_decode_with(&encoded, config), encoding::base64_decode_with(&encoded, config), "{} {:?}", ctx, config);
+
+                let corrupt = gen.corrupt(&encoded, BASE64_CORRUPTIONS);
+                assert_eq!(swar::base64_decode_with(&corrupt, config), encoding::base64_decode_with(&corrupt, config), "{} {:?} {:?}", ctx, config, corrupt);
+            }
+        }
+    }
+
+    // every encoding::simd function against its encoding twin, on valid and corrupted input
+    #[cfg(feature = "simd")]
+    fn check_simd_matches_scalar<const N: usize>(gen: &mut Gen) {
+        for case in 0..CASES {
+            let bytes = gen.bytes(MAX_LEN);
+            let mut other = vec![0u8; bytes.len()];
+            gen.rng.fill(&mut other[..]);
+            let key = gen.bytes(3*N);
+            let ctx = format!("{} with {} lanes", gen.context(case), N);
+
+            // XOR
+            assert_eq!(simd::xor_bytes::<N>(&bytes, &other), encoding::xor_bytes(&bytes, &other), "{}", ctx);
+            assert_eq!(simd::xor_bytes::<N>(&bytes, &key), encoding::xor_bytes(&bytes, &key), "{}", ctx);
+
+            let (mut simd_out, mut scalar_out) = (bytes.clone(), bytes.clone());
+            assert_eq!(simd::xor_assign::<N>(&mut simd_out, &other), encoding::xor_assign(&mut scalar_out, &other), "{}", ctx);
+            assert_eq!(simd_out, scalar_out, "{}", ctx);
+
+            assert_eq!(simd::xor_into::<N>(&mut simd_out, &bytes, &other), encoding::xor_into(&mut scalar_out, &bytes, &other), "{}", ctx);
+            assert_eq!(simd_out, scalar_out, "{}", ctx);
+
+            assert_eq!(simd::xor_with_key::<N>(&bytes, &key), encoding::xor_with_key(&bytes, &key), "{}", ctx);
+
+            let (mut simd_out, mut scalar_out) = (bytes.clone(), bytes.clone());
+            simd::xor_with_keystream::<N>(&mut simd_out, key.iter().cloned());
+            encoding::xor_with_keystream(&mut scalar_out, key.iter().cloned());
+            assert_eq!(simd_out, scalar_out, "{}", ctx);
+
+            // hex
+            let hex = encoding::hex_encode(&bytes);
+            assert_eq!(simd::hex_encode::<N>(&bytes), hex, "{}", ctx);
+            assert_eq!(simd::hex_decode::<N>(&hex), encoding::hex_decode(&hex), "{}", ctx);
+            for config in HEX_CONFIGS.iter() {
+                let encoded = encoding::hex_encode_with(&bytes, config);
+                assert_eq!(simd::hex_encode_with::<N>(&bytes, config), encoded, "{}", ctx);
+                assert_eq!(simd::hex_decode_with::<N>(&encoded, config), encoding::hex_decode_with(&encoded, config), "{}", ctx);
+
+                let corrupt = gen.corrupt(&encoded, HEX_CORRUPTIONS);
+                assert_eq!(simd::hex_decode_with::<N>(&corrupt, config), encoding::hex_decode_with(&corrupt, config), "{} {:?}", ctx, corrupt);
+            }
+
+            let corrupt = gen.corrupt(&hex, HEX_CORRUPTIONS);
+            assert_eq!(simd::hex_decode::<N>(&corrupt), encoding::hex_decode(&corrupt), "{} {:?}", ctx, corrupt);
+            assert_eq!(simd::hex_to_ascii_str::<N>(&hex), encoding::hex_to_ascii_str(&hex), "{}", ctx);
+            assert_eq!(simd::hex_to_ascii_str::<N>(&corrupt), encoding::hex_to_ascii_str(&corrupt), "{}", ctx);
+            assert_eq!(simd::hex_to_base64::<N>(&hex), encoding::hex_to_base64(&hex), "{}", ctx);
+            assert_eq!(simd::hex_to_base64::<N>(&corrupt), encoding::hex_to_base64(&corrupt), "{}", ctx);
+
+            let text: String = bytes.iter().map(|&b| (b & 0x7f) as char).collect();
+            assert_eq!(simd::ascii_to_hex_str::<N>(&text), encoding::ascii_to_hex_str(&text), "{}", ctx);
+
+            // base64
+            let b64 = encoding::base64_encode(&bytes);
+            assert_eq!(simd::base64_encode::<N>(&bytes), b64, "{}", ctx);
+            assert_eq!(simd::bytes_to_base64::<N>(&bytes), encoding::bytes_to_base64(&bytes), "{}", ctx);
+            assert_eq!(simd::base64_decode::<N>(&b64), encoding::base64_decode(&b64), "{}", ctx);
+            assert_eq!(simd::base64_to_ascii_str::<N>(&b64), encoding::base64_to_ascii_str(&b64), "{}", ctx);
+
+            let corrupt = gen.corrupt(&b64, BASE64_CORRUPTIONS);
+            assert_eq!(simd::base64_decode::<N>(&corrupt), encoding::base64_decode(&corrupt), "{} {:?}", ctx, corrupt);
+            assert_eq!(simd::base64_to_ascii_str::<N>(&corrupt), encoding::base64_to_ascii_str(&corrupt), "{} {:?}", ctx, corrupt);
+
+            for config in BASE64_CONFIGS.iter() {
+                let encoded = encoding::base64_encode_with(&bytes, config);
+                assert_eq!(simd::base64_encode_with::<N>(&bytes, config), encoded, "{} {:?}", ctx, config);
+                assert_eq!(simd::base64_decode_with::<N>(&encoded, config), encoding::base64_decode_with(&encoded, config), "{} {:?}", ctx, config);
+
+                let corrupt = gen.corrupt(&encoded, BASE64_CORRUPTIONS);
+                assert_eq!(simd::base64_decode_with::<N>(&corrupt, config), encoding::base64_decode_with(&corrupt, config), "{} {:?} {:?}", ctx, config, corrupt);
+            }
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn prop_simd_matches_scalar() {
+        let mut gen = Gen::new("prop_simd_matches_scalar");
+        // widths that are not a power of two leave lanes unused in the base64 groups
+        check_simd_matches_scalar::<6>(&mut gen);
+        check_simd_matches_scalar::<12>(&mut gen);
+        check_simd_matches_scalar::<16>(&mut gen);
+        check_simd_matches_scalar::<32>(&mut gen);
+        check_simd_matches_scalar::<64>(&mut gen);
+    }
+}
@@ -1,255 +1,166 @@
 #[cfg(test)]
 mod tests{
+    use crate::analysis::*;
+    use crate::analysis::ngram::NgramScorer;
+    use crate::attacks::*;
+    use crate::ciphers::*;
     use crate::encoding::*;
-    use bitvec::prelude::*;
-    use std::collections::HashMap;
-    use std::fs::{self, File};
-    use std::io::{self, BufRead};
-    use std::path::Path;
-
-    fn read_lines<P>(filename: P) -> io::Result<String>
-    where P: AsRef<Path>, {
-        let file = File::open(filename)?;
-
-        let mut s = String::new();
-        for line in io::BufReader::new(file).lines() {
-            if let Ok(ip) = line {
-                s.push_str(&ip)
-            }
-        }
-        Ok(s)
-    }
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use std::fs;
 
     // set 1 challenge 1: convert hex to base64
     #[test]
     fn set1_challenge1() {
-        let result = hex_to_base64("49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d");
+        let result = ByteBuf::from_hex("49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d").unwrap().to_base64();
         assert_eq!(result, "SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t");
     }
 
     // set 1 challenge 2: XOR two hex buffers
     #[test]
     fn set1_challenge2() {
-        let buf1: String = hex_to_ascii_str("1c0111001f010100061a024b53535009181c");
-        let buf2: String = hex_to_ascii_str("686974207468652062756c6c277320657965");
-        let out = xor_bytes(&buf1.as_bytes(), &buf2.as_bytes());
-        let str_out = String::from_utf8(out).unwrap();
+        let buf1: ByteBuf = "1c0111001f010100061a024b53535009181c".parse().unwrap();
+        let buf2: ByteBuf = "686974207468652062756c6c277320657965".parse().unwrap();
 
         let expected: &str = "746865206b696420646f6e277420706c6179";
-        let xored_bytes: String = ascii_to_hex_str(&str_out);
+        let xored_bytes: String = (&buf1 ^ &buf2).to_string();
 
         assert_eq!(expected, xored_bytes);
     }
 
-    fn get_english_letter_freq() -> HashMap<char, f64> {
-        [
-            ('a', 0.0651738),
-            ('b', 0.0124248),
-            ('c', 0.0217339),
-            ('d', 0.0349835),
-            ('e', 0.1041442),
-            ('f', 0.0197881),
-            ('g', 0.0158610),
-            ('h', 0.0492888),
-            ('i', 0.0558094),
-            ('j', 0.0009033),
-            ('k', 0.0050529),
-            ('l', 0.0331490),
-            ('m', 0.0202124),
-            ('n', 0.0564513),
-            ('o', 0.0596302),
-            ('p', 0.0137645),
-            ('q', 0.0008606),
-            ('r', 0.0497563),
-            ('s', 0.0515760),
-            ('t', 0.0729357),
-            ('u', 0.0225134),
-            ('v', 0.0082903),
-            ('w', 0.0171272),
-            ('x', 0.0013692),
-            ('y', 0.0145984),
-            ('z', 0.0007836),
-            (' ', 0.1918182)
-        ].iter().cloned().collect()
-    }
-
-    // encrypt ascii text with a 1-byte key
-    fn xor_single_char(s: &str, k: char) -> String {
-        let mut ciphertext: String = String::new();
-
-        for c in s.chars() {
-            ciphertext.push(((c as u8) ^ (k as u8)) as char);
-        }
-
-        return ciphertext;
-    }
-
-    // assumption: characters are independent samples from the distribution defined by english_letter_freq
-    // the higher the plaintext score, the "more likely" the plaintext is
-    fn plaintext_score(english_letter_freq: &HashMap<char, f64>, plaintext: &str) -> f64 {
-        let mut score: f64 = 0.0;
-        for c in plaintext.chars() {
-            // if no character occurred in the plaintext, set frequency difference to 1.0
-            // this creates a penalty against plaintexts that don't have letters
-            score += english_letter_freq.get(&c).unwrap_or(&0.0);
-        }
-        return score;
-    }
-
     // set 1 challenge 3: determine 1-byte encryption key
     #[test]
     fn set1_challenge3() {
-        let english_letter_freq = get_english_letter_freq();
-        let s: String = hex_to_ascii_str("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736");
+        let ciphertext: ByteBuf = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736".parse().unwrap();
 
-        let mut best_score: f64 = 0.0;
-        let mut best_plaintext: String = "".to_string();
+        let (key, _) = break_single_byte_xor(&ciphertext, &LetterFrequencyScorer::english());
+        assert_eq!(b"Cooking MC's like a pound of bacon", &single_byte_xor(&ciphertext, key)[..]);
+    }
 
-        for k in 0u8..255 {
-            let plaintext: String = xor_single_char(&s, k as char);
-            let score: f64 = plaintext_score(&english_letter_freq, &plaintext.to_ascii_lowercase());
+    // the seeds of the random workloads in set1_scorer_comparison, and how many
+    // random repeating keys each one gets
+    const SEEDS: [u64; 4] = [1, 2, 3, 20200118];
+    const LONG_KEYS: usize = 4;
 
-            if score > best_score {
-                best_score = score;
-                best_plaintext = plaintext;
-            }
-        }
+    // the ciphertexts of challenges 4 (hex, one per line) and 6 (line-wrapped base64)
+    fn load_4txt() -> Vec<ByteBuf> {
+        let filestr: String = fs::read_to_string("4.txt").expect("cannot read 4.txt");
+        return filestr.lines().map(|line| line.parse().expect("4.txt contains invalid hex")).collect();
+    }
 
-        assert_eq!("Cooking MC's like a pound of bacon", best_plaintext);
+    fn load_6txt() -> ByteBuf {
+        let filestr: String = fs::read_to_string("6.txt").expect("cannot read 6.txt");
+        return ByteBuf::from_base64_with(&filestr, &Base64Config::MIME).expect("6.txt contains invalid base64");
     }
 
+    // set 1 challenge 4: find the line encrypted with 1-byte XOR
     #[test]
     fn set1_challenge4() {
-        let english_letter_freq = get_english_letter_freq();
-        let filestr: String =
-            fs::read_to_string("4.txt")
-            .expect("cannot read 4.txt");
-
-        let mut best_score: f64 = 0.0;
-        let mut best_plaintext: String = String::new();
-        for line in filestr.lines() {
-            let line_ascii: String = hex_to_ascii_str(line);
-            for k in 0u8..255 {
-                let plaintext: String = xor_single_char(&line_ascii, k as char).trim().to_string();
-                let score: f64 = plaintext_score(&english_letter_freq, &plaintext.to_ascii_lowercase());
-
-                if score > best_score {
-                    best_score = score;
-                    best_plaintext = plaintext;
-                }
-            }
-        }
-
-        assert_eq!("Now that the party is jumping", best_plaintext);
-    }
-
-    // encrypt ascii text with a 1-byte key
-    fn xor_repeating_key(plaintext: &str, key: &str) -> String {
-        let key_bytes = key.as_bytes();
-        let key_len = key_bytes.len();
-        let mut ciphertext: String = String::new();
-        let mut i: usize = 0;
-
-        for c in plaintext.chars() {
-            let key_i: usize = i % key_len;
-            let key_char: char = key_bytes[key_i] as char;
-            ciphertext.push(((c as u8) ^ (key_char as u8)) as char);
-            i += 1;
-        }
-
-        println!("key: {}; key_len: {};  ciphertext: {}", key, key_len, ciphertext);
-
-        return ciphertext;
+        let lines = load_4txt();
+        let (i, key, _) = detect_single_byte_xor(&lines, &LetterFrequencyScorer::english()).unwrap();
+        let plaintext = single_byte_xor(&lines[i], key);
+        assert_eq!(b"Now that the party is jumping", plaintext.trim_ascii());
     }
 
+    // set 1 challenge 5: repeating-key XOR
     #[test]
     fn set1_challenge5() {
         let line = "Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
-        let ciphertext=  ascii_to_hex_str(&xor_repeating_key(line, "ICE"));
+        let ciphertext = ByteBuf(repeating_key_xor(line.as_bytes(), b"ICE").unwrap()).to_string();
 
         assert_eq!(ciphertext, "0b3637272a2b2e63622c2e69692a23693a2a3c6324202d623d63343c2a26226324272765272a282b2f20430a652e2c652a3124333a653e2b2027630c692b20283165286326302e27282f");
     }
 
-    fn hamming_distance(str1: &[u8], str2: &[u8]) -> u64 {
-        let bits1 = str1.view_bits::<Msb0>();
-        let bits2 = str2.view_bits::<Msb0>();
-
-        let mut biter2 = bits2.iter();
-        let mut count = 0;
-        for b1 in bits1.iter() {
-            let xor = *b1 ^ *(biter2.next().expect("hamming_distance: strings have to be same length"));
-            count += if xor { 1 } else { 0 };
-        }
-        return count;
-    }
-
+    // set 1 challenge 6: break repeating-key XOR
     #[test]
-    fn test_hamming() {
-        let d = hamming_distance("this is a test".as_bytes(), "wokka wokka!!!".as_bytes());
-        assert_eq!(d, 37);
-    }
+    fn set1_challenge6() {
+        let filebytes = load_6txt();
 
-    // calculate the average Hamming distance given a specific window size
-    fn window_hamming_distance(s: &[u8], size: usize) -> f64 {
-        let mut i: usize = 0;
-        let mut d: u64 = 0;
-        while (i+2)*size < s.len() {
-            let frame1 = &s[(i*size) .. (i+1)*size];
-            let frame2 = &s[(i+1)*size .. (i+2)*size];
-            d += hamming_distance(frame1, frame2);
-            i += 1;
-        }
+        let key = break_repeating_key_xor(&filebytes, &LetterFrequencyScorer::english());
+        assert_eq!(&key[..], b"Terminator X: Bring the noise");
 
-        return (d as f64) / (s.len() as f64);
+        let plaintext = repeating_key_xor(&filebytes, &key).unwrap();
+        assert!(plaintext.starts_with(b"I'm back and I'm ringin' the bell"));
     }
 
+    // how often each scorer picks the right key. besides the 4.txt and 6.txt challenges
+    // themselves, the 6.txt plaintext is cut into 12- and 30-byte lines like those of 4.txt
+    // and encrypted with random single-byte keys, and encrypted whole with random repeating
+    // keys. the random workloads are repeated for several seeds, and the comparisons
+    // below have to hold for each of them
     #[test]
-    fn set1_challenge6() {
-        // load file and decode from base64
-        let filestr: String =
-            base64_to_ascii_str(
-                &read_lines("6.txt")
-                .expect("cannot read 6.txt")
-            );
-
-        // find keysize
-        let filebytes = filestr.as_bytes();
-        let mut best_d = window_hamming_distance(filebytes, 1);
-        let mut best_key_size: usize = 1;
-        for window_size in 2 .. 50 {
-            let d = window_hamming_distance(filebytes, window_size);
-            if d < best_d {
-                best_d = d;
-                best_key_size = window_size;
-            }
+    fn set1_scorer_comparison() {
+        let lines = load_4txt();
+        let line_plaintext = b"Now that the party is jumping\n";
+        let (line_index, line_key) = (0..lines.len())
+            .flat_map(|i| (0u8..=255).map(move |k| (i, k)))
+            .find(|&(i, k)| single_byte_xor(&lines[i], k) == line_plaintext)
+            .unwrap();
+
+        let ciphertext = load_6txt();
+        let key = b"Terminator X: Bring the noise";
+        let plaintext = repeating_key_xor(&ciphertext, key).unwrap();
+
+        let scorers: [(&str, &dyn PlaintextScorer); 5] = [
+            ("frequency sum", &LetterFrequencyScorer::english()),
+            ("chi-squared", &ChiSquaredScorer::english()),
+            ("log-likelihood", &LogLikelihoodScorer::english()),
+            ("bhattacharyya", &BhattacharyyaScorer::english()),
+            ("4-grams", &NgramScorer::english(4).unwrap()),
+        ];
+        for (name, scorer) in scorers.iter() {
+            let found_line = detect_single_byte_xor(&lines, *scorer)
+                .is_some_and(|(i, k, _)| (i, k) == (line_index, line_key));
+            let found_key = break_repeating_key_xor(&ciphertext, *scorer) == key;
+            assert!(found_line && found_key, "{} fails a challenge", name);
         }
 
-        // arrange blocks to find individual characters of key
-        // e.g. if key is length 3, arrange bytes 1, 4, 7, ... into one block;
-        // arrange bytes 2, 5, 8, ... into another block; and so on
-        let english_letter_freq = get_english_letter_freq();
-        let mut blocks = vec![String::new(); best_key_size];
-        for i in 0 .. filebytes.len() {
-            blocks[i % best_key_size].push(filebytes[i] as char);
-        }
+        let mut totals = [[0; 3]; 5];
+        for seed in SEEDS.iter() {
+            let mut rng = StdRng::seed_from_u64(*seed);
+            let short_keys: Vec<u8> = plaintext.chunks_exact(12).map(|_| rng.gen()).collect();
+            let long_keys: Vec<Vec<u8>> = (0..LONG_KEYS).map(|_| {
+                let len = rng.gen_range(2..=200);
+                (0..len).map(|_| rng.gen()).collect()
+            }).collect();
+
+            // hits on 12-byte lines, 30-byte lines and random repeating key bytes, per scorer
+            let mut hits: Vec<[usize; 3]> = Vec::new();
+            for (_, scorer) in scorers.iter() {
+                let mut scorer_hits = [0; 3];
+                for (line_hits, line_len) in scorer_hits.iter_mut().zip([12, 30]) {
+                    *line_hits = plaintext.chunks_exact(line_len).zip(short_keys.iter())
+                        .filter(|&(chunk, &k)| break_single_byte_xor(&single_byte_xor(chunk, k), *scorer).0 == k)
+                        .count();
+                }
 
-        let mut key = String::new();
-        for i in 0 .. best_key_size {
-            let mut best_score: f64 = 0.0;
-            let mut best_char: char = ' ';
-            for k in 0u8..255 {
-                let plaintext: String = xor_single_char(&blocks[i], k as char);
-                let score: f64 = plaintext_score(&english_letter_freq, &plaintext.to_ascii_lowercase());
-
-                if score > best_score {
-                    best_score = score;
-                    best_char = k as char;
+                // the key size is given, so that only the scorer is being compared
+                for k in long_keys.iter() {
+                    let recovered = recover_repeating_key(&repeating_key_xor(&plaintext, k).unwrap(), k.len(), *scorer);
+                    scorer_hits[2] += recovered.iter().zip(k.iter()).filter(|(a, b)| a == b).count();
                 }
+                hits.push(scorer_hits);
+            }
+            let long_total: usize = long_keys.iter().map(|k| k.len()).sum();
+
+            // the log-likelihood and Bhattacharyya scorers do at least as well as the
+            // frequency sum, and the 4-gram model, which sees the order of the bytes,
+            // at least as well as any of them and gets nearly every key byte
+            let at_least = |a: &[usize; 3], b: &[usize; 3]| a[0] >= b[0] && a[2] >= b[2];
+            assert!(at_least(&hits[2], &hits[0]) && at_least(&hits[3], &hits[0]), "seed {}: {:?}", seed, hits);
+            assert!(hits[..4].iter().all(|h| at_least(&hits[4], h)), "seed {}: {:?}", seed, hits);
+            assert!(hits[4][2] * 100 >= long_total * 99, "seed {}: {:?} of {}", seed, hits, long_total);
+            // lines as long as 4.txt's are easy for all of them
+            assert!(hits.iter().all(|h| h[1] * 10 >= (plaintext.len() / 30) * 9), "seed {}: {:?}", seed, hits);
+
+            for (total, h) in totals.iter_mut().zip(hits.iter()) {
+                total.iter_mut().zip(h.iter()).for_each(|(t, x)| *t += x);
             }
-
-            key.push(best_char as char);
         }
 
-        assert_eq!(key, "Terminator X: Bring the noise");
+        // over all the seeds together, each of those is strictly better
+        let better = |a: &[usize; 3], b: &[usize; 3]| a[0] > b[0] && a[2] > b[2];
+        assert!(better(&totals[2], &totals[0]) && better(&totals[3], &totals[0]), "{:?}", totals);
+        assert!(totals[..4].iter().all(|t| better(&totals[4], t)), "{:?}", totals);
     }
-}
\ No newline at end of file
+}
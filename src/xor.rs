@@ -0,0 +1,36 @@
+use bitvec::prelude::*;
+use crate::encoding::EncodingError;
+
+// the XOR routines live with the other byte-level conversions in encoding;
+// they are re-exported here so that users of the ciphers find them in one place
+pub use crate::encoding::{xor_bytes, xor_assign, xor_into, xor_with_key, xor_with_keystream};
+
+// the number of bits that differ between two strings of the same length
+pub fn hamming_distance(str1: &[u8], str2: &[u8]) -> Result<u64, EncodingError> {
+    if str1.len() != str2.len() {
+        return Err(EncodingError::LengthMismatch { left: str1.len(), right: str2.len() });
+    }
+
+    let bits1 = str1.view_bits::<Msb0>();
+    let bits2 = str2.view_bits::<Msb0>();
+
+    let mut count = 0;
+    for (b1, b2) in bits1.iter().zip(bits2.iter()) {
+        count += if *b1 ^ *b2 { 1 } else { 0 };
+    }
+    return Ok(count);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hamming() {
+        let d = hamming_distance("this is a test".as_bytes(), "wokka wokka!!!".as_bytes());
+        assert_eq!(d, Ok(37));
+        assert_eq!(hamming_distance(b"", b""), Ok(0));
+        assert_eq!(hamming_distance(b"abc", b"ab"), Err(EncodingError::LengthMismatch { left: 3, right: 2 }));
+        assert_eq!(hamming_distance(b"ab", b"abc"), Err(EncodingError::LengthMismatch { left: 2, right: 3 }));
+    }
+}